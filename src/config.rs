@@ -1,6 +1,8 @@
-use crate::state::Config;
+use crate::state::{Config, PaletteConfig, ScratchBuffer};
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
 
 pub fn get_config_path() -> PathBuf {
     let mut path = std::env::current_exe().unwrap_or_else(|_| PathBuf::from("."));
@@ -9,19 +11,107 @@ pub fn get_config_path() -> PathBuf {
     path
 }
 
-pub fn load_config() -> Config {
-    let path = get_config_path();
-    if let Ok(content) = fs::read_to_string(path) {
-        if let Ok(config) = toml::from_str(&content) {
-            return config;
+pub fn load_config() -> (Config, Option<String>) {
+    load_config_from(&get_config_path())
+}
+
+pub fn load_config_from(path: &Path) -> (Config, Option<String>) {
+    let Ok(content) = fs::read_to_string(path) else {
+        return (Config::default(), None);
+    };
+    match toml::from_str::<Config>(&content) {
+        Ok(mut config) => {
+            config.sanitize();
+            (config, None)
+        }
+        Err(_) => {
+            let backup = backup_path(path);
+            let warning = match fs::copy(path, &backup) {
+                Ok(_) => format!(
+                    "CONFIG INVALID, USING DEFAULTS (BACKUP: {})",
+                    backup.display()
+                ),
+                Err(_) => "CONFIG INVALID, USING DEFAULTS".to_string(),
+            };
+            (Config::default(), Some(warning))
         }
     }
-    Config::default()
+}
+
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
 }
 
 pub fn save_config(config: &Config) -> std::io::Result<()> {
-    let path = get_config_path();
+    save_config_to(config, &get_config_path())
+}
+
+pub fn save_config_to(config: &Config, path: &Path) -> std::io::Result<()> {
     let content = toml::to_string_pretty(config).unwrap_or_default();
     fs::write(path, content)
 }
- 
\ No newline at end of file
+
+#[derive(Default, Serialize, Deserialize)]
+struct ScratchFile {
+    #[serde(default)]
+    buffers: Vec<ScratchBuffer>,
+}
+
+pub fn get_scratch_path() -> PathBuf {
+    let mut path = get_config_path();
+    path.pop();
+    path.push("scratch.toml");
+    path
+}
+
+pub fn save_scratch(buffers: Vec<ScratchBuffer>, path: &Path) -> io::Result<()> {
+    if buffers.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    let content = toml::to_string_pretty(&ScratchFile { buffers })
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::write(path, content)
+}
+
+pub fn load_scratch(path: &Path) -> Vec<ScratchBuffer> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| toml::from_str::<ScratchFile>(&content).ok())
+        .unwrap_or_default()
+        .buffers
+}
+
+pub fn get_theme_path(name: &str) -> Option<PathBuf> {
+    let name = name.trim().trim_end_matches(".toml");
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return None;
+    }
+    let mut path = get_config_path();
+    path.pop();
+    path.push("themes");
+    path.push(format!("{}.toml", name));
+    Some(path)
+}
+
+pub fn export_theme(palette: &PaletteConfig, path: &Path) -> io::Result<()> {
+    let content = toml::to_string_pretty(palette)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)
+}
+
+pub fn import_theme(path: &Path) -> Result<PaletteConfig, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let palette: PaletteConfig = toml::from_str(&content).map_err(|e| e.message().to_string())?;
+    if let Some(name) = palette.invalid_entry() {
+        return Err(format!("BAD COLOR FOR {}", name));
+    }
+    Ok(palette)
+}
@@ -0,0 +1,1954 @@
+use crate::state::{
+    help_visible_rows, AppState, Buffer, ChordOutcome, Config, ConfirmChoice, ConfirmType,
+    FindCache, IndentStyle, KeyCombo, KeybindAction, MenuTab, Mode, Palette, PaletteConfig,
+    PromptType, Selection, WipeConfirm, COLOR_EDITOR_ROWS, DIR_MARKER, EXPLORER_BATCH, FILE_MARKER,
+    HELP_ROWS, OUTLINE_ROWS, WIPE_CONFIRM_LINES, WIPE_CONFIRM_WORD,
+};
+use crate::{config, editor, ui};
+
+use chrono::Local;
+use crossterm::{
+    event::{poll, KeyCode, KeyEvent, KeyModifiers},
+    terminal::size,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+const HSCROLL_STEP: isize = 4;
+const CHORD_TIMEOUT: Duration = Duration::from_millis(1000);
+
+pub fn cursor_position(app: &AppState) -> (usize, usize, usize) {
+    let buf = app.current_buffer();
+    (app.active_buffer, buf.cursor_x, buf.cursor_y)
+}
+
+pub fn handle_key_event(
+    app: &mut AppState,
+    key: KeyEvent,
+    mode: &mut Mode,
+    active_tab: &mut MenuTab,
+    dropdown_idx: &mut usize,
+    config: &mut Config,
+) -> std::io::Result<bool> {
+    app.hint_dismissed = true;
+    match overlay_action(*mode, key.code) {
+        Some(OverlayAction::Close) => {
+            *mode = overlay_parent(*mode);
+            return Ok(false);
+        }
+        Some(OverlayAction::Confirm) => {
+            return confirm_overlay(app, mode, *active_tab, *dropdown_idx, config);
+        }
+        None => {}
+    }
+
+    match *mode {
+        Mode::ConfirmWipe => match app.current_buffer().wipe_confirm(WIPE_CONFIRM_LINES) {
+            WipeConfirm::Quick => {
+                if matches!(key.code, KeyCode::Char('y' | 'Y')) {
+                    wipe_current_buffer(app);
+                } else {
+                    app.flash_status("WIPE CANCELLED".to_string());
+                }
+                *mode = Mode::Editing;
+            }
+            WipeConfirm::Typed => match key.code {
+                KeyCode::Char(c) => app.wipe_input.push(c),
+                KeyCode::Backspace => {
+                    app.wipe_input.pop();
+                }
+                KeyCode::Enter if app.wipe_input.eq_ignore_ascii_case(WIPE_CONFIRM_WORD) => {
+                    wipe_current_buffer(app);
+                    app.wipe_input.clear();
+                    *mode = Mode::Editing;
+                }
+                _ => {
+                    app.flash_status("WIPE CANCELLED".to_string());
+                    app.wipe_input.clear();
+                    *mode = Mode::Editing;
+                }
+            },
+        },
+
+        Mode::Confirm(ConfirmType::Overwrite) => {
+            if let Some(target) = app.pending_rename.take() {
+                if matches!(key.code, KeyCode::Char('y' | 'Y')) {
+                    rename_current_file(app, target, config);
+                } else {
+                    app.flash_status("RENAME CANCELLED".to_string());
+                }
+            }
+            *mode = Mode::Editing;
+        }
+
+        Mode::Confirm(ConfirmType::CloseTab) => {
+            let mut close_tab = false;
+            let mut save_and_close = false;
+
+            match key.code {
+                KeyCode::Up | KeyCode::Left => {
+                    app.confirm_choice = match app.confirm_choice {
+                        ConfirmChoice::No => ConfirmChoice::Cancel,
+                        ConfirmChoice::Yes => ConfirmChoice::No,
+                        ConfirmChoice::Cancel => ConfirmChoice::Yes,
+                    };
+                }
+                KeyCode::Down | KeyCode::Right => {
+                    app.confirm_choice = match app.confirm_choice {
+                        ConfirmChoice::No => ConfirmChoice::Yes,
+                        ConfirmChoice::Yes => ConfirmChoice::Cancel,
+                        ConfirmChoice::Cancel => ConfirmChoice::No,
+                    };
+                }
+                KeyCode::Enter => {
+                    match app.confirm_choice {
+                        ConfirmChoice::Yes => save_and_close = true,
+                        ConfirmChoice::No => close_tab = true,
+                        ConfirmChoice::Cancel => {}
+                    }
+                    app.confirm_mode = None;
+                    *mode = Mode::Editing;
+                }
+                KeyCode::Esc => {
+                    app.confirm_mode = None;
+                    *mode = Mode::Editing;
+                }
+                _ => {}
+            }
+
+            if save_and_close {
+                save_and_close_tab(app, config);
+            } else if close_tab {
+                close_current_tab(app);
+            }
+        }
+
+        Mode::Help => match key.code {
+            KeyCode::Up => app.help_offset = app.help_offset.saturating_sub(1),
+            KeyCode::Down => {
+                let (_, term_h) = size().unwrap_or((80, 24));
+                let hidden = HELP_ROWS - help_visible_rows(term_h);
+                app.help_offset = (app.help_offset + 1).min(hidden);
+            }
+            _ => {}
+        },
+
+        Mode::BufferInfo => {}
+
+        Mode::Outline => match key.code {
+            KeyCode::Up if app.outline_idx > 0 => {
+                app.outline_idx -= 1;
+                if app.outline_idx < app.outline_offset {
+                    app.outline_offset = app.outline_idx;
+                }
+            }
+            KeyCode::Down if app.outline_idx + 1 < app.outline.len() => {
+                app.outline_idx += 1;
+                if app.outline_idx >= app.outline_offset + OUTLINE_ROWS {
+                    app.outline_offset = app.outline_idx + 1 - OUTLINE_ROWS;
+                }
+            }
+            _ => {}
+        },
+
+        Mode::Settings => match key.code {
+            KeyCode::Up => app.settings_idx = app.settings_idx.saturating_sub(1),
+            KeyCode::Down if app.settings_idx < 4 => app.settings_idx += 1,
+            _ => {}
+        },
+
+        Mode::KeyRebind => {
+            let mut flash_msg: Option<String> = None;
+            {
+                let kb = &mut app.keybind_state;
+                let total_actions = 23;
+
+                if kb.waiting_for_key {
+                    if let Some(index) = kb.pending_action {
+                        if let Some(action) = KeybindAction::from_index(index) {
+                            let combo = KeyCombo {
+                                code: key.code,
+                                modifiers: key.modifiers,
+                            };
+                            kb.custom_binds.insert(vec![combo], action);
+
+                            save_keybind_to_config(config, &[combo], action);
+                            let _ = config::save_config(config);
+
+                            let mod_str = if key.modifiers.contains(KeyModifiers::CONTROL) {
+                                "Ctrl+"
+                            } else if key.modifiers.contains(KeyModifiers::SHIFT) {
+                                "Shift+"
+                            } else if key.modifiers.contains(KeyModifiers::ALT) {
+                                "Alt+"
+                            } else {
+                                ""
+                            };
+                            let key_str = format!("{:?}", key.code);
+                            flash_msg = Some(format!(
+                                "{} BOUND & SAVED",
+                                format!("{mod_str}{key_str}").trim()
+                            ));
+                        }
+                    }
+                    kb.waiting_for_key = false;
+                    kb.pending_action = None;
+                } else if kb.confirming_reset {
+                    if matches!(key.code, KeyCode::Char('y' | 'Y')) {
+                        kb.custom_binds.clear();
+                        config.custom_keybinds.clear();
+                        let _ = config::save_config(config);
+                        flash_msg = Some("ALL KEYBINDS RESET & SAVED".to_string());
+                    } else {
+                        flash_msg = Some("RESET CANCELLED".to_string());
+                    }
+                    kb.confirming_reset = false;
+                } else {
+                    match key.code {
+                        KeyCode::Esc => {
+                            *mode = Mode::Settings;
+                            kb.in_rebind_mode = false;
+                        }
+                        KeyCode::Up if kb.selected_action > 0 => {
+                            kb.selected_action -= 1;
+                            if kb.selected_action < kb.scroll_offset {
+                                kb.scroll_offset = kb.selected_action;
+                            }
+                        }
+                        KeyCode::Down if kb.selected_action < total_actions - 1 => {
+                            kb.selected_action += 1;
+                            if kb.selected_action >= kb.scroll_offset + 14 {
+                                kb.scroll_offset = kb.selected_action.saturating_sub(13);
+                            }
+                        }
+                        KeyCode::Enter => {
+                            if kb.selected_action == 22 {
+                                kb.confirming_reset = true;
+                                flash_msg = Some(
+                                    "RESET ALL BINDS? Press Y to confirm, anything else cancel"
+                                        .to_string(),
+                                );
+                            } else if KeybindAction::from_index(kb.selected_action).is_some() {
+                                kb.waiting_for_key = true;
+                                kb.pending_action = Some(kb.selected_action);
+                                flash_msg = Some(
+                                    config.glyphs("PRESS NEW KEY • Esc to cancel").into_owned(),
+                                );
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            if let Some(m) = flash_msg {
+                app.flash_status(m);
+            }
+        }
+
+        Mode::ColorEditor if app.input_mode => handle_prompt_input(app, key.code, mode, config),
+
+        Mode::ColorEditor => match key.code {
+            KeyCode::Esc if app.editing_hex => {
+                app.editing_hex = false;
+                let saved = app.current_palette.to_config();
+                let entry = &mut app.color_entries[app.color_editor_idx];
+                if let Some(hex) = saved.get(&entry.name) {
+                    entry.current_hex = hex.to_string();
+                }
+            }
+            KeyCode::Esc => *mode = Mode::Settings,
+            KeyCode::Up => {
+                app.color_editor_idx = app.color_editor_idx.saturating_sub(1);
+                if app.color_editor_idx < app.color_editor_offset {
+                    app.color_editor_offset = app.color_editor_idx;
+                }
+                app.editing_hex = false;
+            }
+            KeyCode::Down => {
+                if app.color_editor_idx < app.color_entries.len() - 1 {
+                    app.color_editor_idx += 1;
+                }
+                if app.color_editor_idx >= app.color_editor_offset + COLOR_EDITOR_ROWS {
+                    app.color_editor_offset = app.color_editor_idx + 1 - COLOR_EDITOR_ROWS;
+                }
+                app.editing_hex = false;
+            }
+            KeyCode::Enter => app.editing_hex = !app.editing_hex,
+            KeyCode::Char('e') if !app.editing_hex => {
+                app.input_mode = true;
+                app.prompt_type = PromptType::ExportTheme;
+                app.input_buffer.clear();
+            }
+            KeyCode::Char('i') if !app.editing_hex => {
+                app.input_mode = true;
+                app.prompt_type = PromptType::ImportTheme;
+                app.input_buffer.clear();
+            }
+            KeyCode::Char('r') if !app.editing_hex => {
+                let defaults = PaletteConfig::default();
+                let entry = &mut app.color_entries[app.color_editor_idx];
+                if let Some(hex) = defaults.get(&entry.name) {
+                    entry.current_hex = hex.to_string();
+                    let msg = format!("{} RESET", entry.name.to_uppercase());
+                    app.flash_status(msg);
+                }
+            }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let palette_config = PaletteConfig::from_entries(&app.color_entries);
+                app.current_palette = Palette::from_config(&palette_config);
+                config.palette = palette_config;
+                let _ = config::save_config(config);
+                app.flash_status("COLORS SAVED".to_string());
+                *mode = Mode::Settings;
+            }
+            _ if app.editing_hex => {
+                let entry = &mut app.color_entries[app.color_editor_idx];
+                match key.code {
+                    KeyCode::Char(c) => {
+                        let c = c.to_ascii_uppercase();
+                        if c.is_ascii_hexdigit() && entry.current_hex.len() < 7 {
+                            if entry.current_hex.is_empty() {
+                                entry.current_hex.push('#');
+                            }
+                            entry.current_hex.push(c);
+                        }
+                    }
+                    KeyCode::Backspace => {
+                        entry.current_hex.pop();
+                        if entry.current_hex == "#" {
+                            entry.current_hex.clear();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        },
+
+        Mode::Explorer => match key.code {
+            KeyCode::Up if app.explorer_idx > 0 => {
+                app.explorer_idx -= 1;
+                if app.explorer_idx < app.explorer_offset {
+                    app.explorer_offset = app.explorer_idx;
+                }
+                update_explorer_preview(app, config);
+            }
+            KeyCode::Down if app.explorer_idx < app.explorer_files.len().saturating_sub(1) => {
+                app.explorer_idx += 1;
+                let (_, term_h) = size().unwrap_or((80, 24));
+                let visible = term_h.saturating_sub(4) as usize;
+                if app.explorer_idx >= app.explorer_offset + visible {
+                    app.explorer_offset = app.explorer_idx - visible + 1;
+                }
+                update_explorer_preview(app, config);
+            }
+            KeyCode::Backspace => {
+                if let Some(parent) = app.current_dir.parent() {
+                    change_explorer_dir(app, parent.to_path_buf(), config);
+                }
+            }
+            _ => {}
+        },
+
+        Mode::Editing => {
+            if app.input_mode {
+                handle_prompt_input(app, key.code, mode, config);
+            } else {
+                if key.code == KeyCode::Tab && key.modifiers.contains(KeyModifiers::CONTROL) {
+                    if app.buffers.len() > 1 {
+                        if key.modifiers.contains(KeyModifiers::SHIFT) {
+                            app.oldest_buffer();
+                        } else {
+                            app.recent_buffer();
+                        }
+                        update_viewport(app, config);
+                        app.flash_status(format!("TAB {}", app.current_buffer().filename));
+                    }
+                    return Ok(false);
+                }
+
+                handle_editing_input(app, key, config, mode);
+            }
+        }
+
+        Mode::Menu => match key.code {
+            KeyCode::Left => {
+                *active_tab = prev_tab(*active_tab);
+                *dropdown_idx = 0;
+            }
+            KeyCode::Right => {
+                *active_tab = next_tab(*active_tab);
+                *dropdown_idx = 0;
+            }
+            KeyCode::Up => *dropdown_idx = dropdown_idx.saturating_sub(1),
+            KeyCode::Down => *dropdown_idx += 1,
+            _ => {}
+        },
+    }
+
+    Ok(false)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverlayAction {
+    Close,
+    Confirm,
+}
+
+const OVERLAY_KEYS: &[(Mode, KeyCode, OverlayAction)] = &[
+    (Mode::Menu, KeyCode::Esc, OverlayAction::Close),
+    (Mode::Menu, KeyCode::Enter, OverlayAction::Confirm),
+    (Mode::Help, KeyCode::Esc, OverlayAction::Close),
+    (Mode::Help, KeyCode::Enter, OverlayAction::Close),
+    (Mode::BufferInfo, KeyCode::Esc, OverlayAction::Close),
+    (Mode::BufferInfo, KeyCode::Enter, OverlayAction::Close),
+    (Mode::Outline, KeyCode::Esc, OverlayAction::Close),
+    (Mode::Outline, KeyCode::Enter, OverlayAction::Confirm),
+    (Mode::Settings, KeyCode::Esc, OverlayAction::Close),
+    (Mode::Settings, KeyCode::Enter, OverlayAction::Confirm),
+    (Mode::Explorer, KeyCode::Esc, OverlayAction::Close),
+    (Mode::Explorer, KeyCode::Enter, OverlayAction::Confirm),
+];
+
+pub fn overlay_action(mode: Mode, code: KeyCode) -> Option<OverlayAction> {
+    OVERLAY_KEYS
+        .iter()
+        .find(|(overlay, key, _)| *overlay == mode && *key == code)
+        .map(|&(_, _, action)| action)
+}
+
+fn overlay_parent(mode: Mode) -> Mode {
+    match mode {
+        Mode::Explorer => Mode::Menu,
+        _ => Mode::Editing,
+    }
+}
+
+fn confirm_overlay(
+    app: &mut AppState,
+    mode: &mut Mode,
+    active_tab: MenuTab,
+    dropdown_idx: usize,
+    config: &mut Config,
+) -> std::io::Result<bool> {
+    match *mode {
+        Mode::Menu => {
+            let (exit, next_mode) = handle_menu_selection(active_tab, dropdown_idx, config, app)?;
+            if exit {
+                return Ok(true);
+            }
+            *mode = next_mode;
+            if *mode == Mode::Explorer {
+                refresh_explorer(app, config);
+            }
+        }
+        Mode::Outline => {
+            if let Some(symbol) = app.outline.get(app.outline_idx) {
+                let line = symbol.line;
+                let buf = app.current_buffer_mut();
+                buf.cursor_y = line.min(buf.lines.len() - 1);
+                buf.cursor_x = 0;
+                app.selection = None;
+            }
+            *mode = Mode::Editing;
+        }
+        Mode::Settings => match app.settings_idx {
+            0 => {
+                config.auto_save = !config.auto_save;
+                let _ = config::save_config(config);
+            }
+            1 => {
+                config.tab_size = if config.tab_size >= 8 {
+                    2
+                } else {
+                    config.tab_size + 2
+                };
+                let _ = config::save_config(config);
+            }
+            2 => {
+                app.populate_color_entries();
+                app.color_editor_idx = 0;
+                app.color_editor_offset = 0;
+                app.editing_hex = false;
+                *mode = Mode::ColorEditor;
+            }
+            3 => {
+                *mode = Mode::KeyRebind;
+                let kb = &mut app.keybind_state;
+                kb.in_rebind_mode = true;
+                kb.selected_action = 0;
+                kb.waiting_for_key = false;
+                kb.pending_action = None;
+                kb.scroll_offset = 0;
+                kb.confirming_reset = false;
+            }
+            4 => *mode = Mode::Editing,
+            _ => {}
+        },
+        Mode::Explorer => {
+            if let Some(selected) = app.explorer_files.get(app.explorer_idx).cloned() {
+                let clean_name = selected
+                    .trim_start_matches(DIR_MARKER)
+                    .trim_start_matches(FILE_MARKER);
+                let full_path = app.current_dir.join(clean_name);
+                if full_path.is_dir() {
+                    change_explorer_dir(app, full_path, config);
+                } else {
+                    match open_file(app, full_path, config) {
+                        Ok(()) => *mode = Mode::Editing,
+                        Err(e) => {
+                            app.flash_status(format!("OPEN FAILED: {}", e));
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+pub fn load_custom_keybinds(app: &mut AppState, config: &mut Config) -> usize {
+    let before = config.custom_keybinds.len();
+    config.custom_keybinds.retain(|(combo_str, action_str)| {
+        match (
+            KeyCombo::parse_sequence(combo_str),
+            action_str.parse::<KeybindAction>().ok(),
+        ) {
+            (Some(sequence), Some(action)) => {
+                app.keybind_state.custom_binds.insert(sequence, action);
+                true
+            }
+            _ => false,
+        }
+    });
+    before - config.custom_keybinds.len()
+}
+
+fn save_keybind_to_config(config: &mut Config, sequence: &[KeyCombo], action: KeybindAction) {
+    let combo_str = KeyCombo::format_sequence(sequence);
+    let action_str = action.to_str().to_string();
+
+    config.custom_keybinds.retain(|(k, _)| k != &combo_str);
+    config.custom_keybinds.push((combo_str, action_str));
+}
+
+pub fn focus_lost_saves(app: &AppState, config: &Config) -> Vec<usize> {
+    if !config.save_on_focus_lost {
+        return Vec::new();
+    }
+    app.buffers
+        .iter()
+        .enumerate()
+        .filter(|(_, buf)| buf.modified && buf.file_path.is_some())
+        .map(|(i, _)| i)
+        .collect()
+}
+
+pub fn save_on_focus_lost(app: &mut AppState, config: &Config) -> usize {
+    let mut saved = 0;
+    for i in focus_lost_saves(app, config) {
+        if config.ensure_final_newline {
+            trim_for_save(app, i);
+        }
+        let buf = &mut app.buffers[i];
+        if editor::save_to_file(&buf.lines, &buf.save_path()).is_ok() {
+            buf.modified = false;
+            buf.last_saved = Some(Local::now());
+            buf.line_ending = "LF";
+            saved += 1;
+        }
+    }
+    if saved > 0 {
+        app.flash_status(format!("AUTO-SAVED {}", saved));
+    }
+    saved
+}
+
+fn trim_for_save(app: &mut AppState, idx: usize) {
+    let lines = &app.buffers[idx].lines;
+    if lines.len() < 2 || !lines[lines.len() - 1].is_empty() {
+        return;
+    }
+    if idx == app.active_buffer {
+        app.find_cache = None;
+    }
+    let limit = app.undo_limit;
+    let buf = &mut app.buffers[idx];
+    buf.push_undo(limit);
+    editor::trim_final_newlines(&mut buf.lines);
+    buf.cursor_y = buf.cursor_y.min(buf.lines.len() - 1);
+    buf.cursor_x = editor::clamp_to_boundary(&buf.lines[buf.cursor_y], buf.cursor_x);
+}
+
+fn save_current_buffer(app: &mut AppState, config: &Config) -> bool {
+    if config.ensure_final_newline {
+        trim_for_save(app, app.active_buffer);
+    }
+    let filename = app.current_buffer().save_path();
+    match editor::save_to_file(&app.current_buffer().lines, &filename) {
+        Ok(()) => {
+            let buf = app.current_buffer_mut();
+            buf.modified = false;
+            buf.last_saved = Some(Local::now());
+            buf.line_ending = "LF";
+            buf.file_path
+                .get_or_insert_with(|| PathBuf::from(&filename));
+            app.flash_status("SAVED".to_string());
+            true
+        }
+        Err(e) => {
+            let reason = editor::describe_io_error(&e);
+            app.flash_status(format!("SAVE FAILED: {}", reason));
+            false
+        }
+    }
+}
+
+fn wipe_current_buffer(app: &mut AppState) {
+    app.push_undo();
+    let buf = app.current_buffer_mut();
+    buf.lines = vec![String::new()];
+    buf.cursor_x = 0;
+    buf.cursor_y = 0;
+    buf.modified = true;
+    app.selection = None;
+    app.flash_status("BUFFER WIPED".to_string());
+}
+
+fn rename_current_file(app: &mut AppState, target: PathBuf, config: &Config) {
+    let on_disk = app
+        .current_buffer()
+        .file_path
+        .as_deref()
+        .is_some_and(Path::exists);
+    if !on_disk {
+        let buf = app.current_buffer_mut();
+        buf.filename = target.to_string_lossy().to_string();
+        buf.file_path = None;
+        save_current_buffer(app, config);
+        return;
+    }
+    match app.rename_current_file(target) {
+        Ok(()) => {
+            let msg = format!("RENAMED TO {}", app.current_buffer().filename);
+            app.flash_status(msg);
+        }
+        Err(e) => {
+            let reason = editor::describe_io_error(&e);
+            app.flash_status(format!("RENAME FAILED: {}", reason));
+        }
+    }
+}
+
+fn save_and_close_tab(app: &mut AppState, config: &Config) {
+    if save_current_buffer(app, config) {
+        close_current_tab(app);
+    }
+}
+
+fn close_current_tab(app: &mut AppState) {
+    app.close_buffer(app.active_buffer);
+}
+
+fn handle_editing_input(app: &mut AppState, key: KeyEvent, config: &Config, mode: &mut Mode) {
+    let code = key.code;
+    let modifiers = key.modifiers;
+
+    let combo = KeyCombo { code, modifiers };
+
+    match app
+        .keybind_state
+        .resolve(combo, Instant::now(), CHORD_TIMEOUT)
+    {
+        ChordOutcome::Matched(action) => {
+            perform_keybind_action(app, action, config, mode);
+            return;
+        }
+        ChordOutcome::Pending => return,
+        ChordOutcome::Mismatch => {
+            app.flash_status("NO CHORD BOUND".to_string());
+            return;
+        }
+        ChordOutcome::Unbound => {}
+    }
+
+    if KeyCombo::from_string(&config.menu_key) == Some(combo) {
+        perform_keybind_action(app, KeybindAction::Menu, config, mode);
+        return;
+    }
+
+    if let Some(action) = KeybindAction::default_for(combo) {
+        perform_keybind_action(app, action, config, mode);
+        return;
+    }
+
+    if let Some(sel) = app.selection.as_ref() {
+        if matches!(code, KeyCode::Backspace | KeyCode::Delete) {
+            let (sx, sy, ex, ey) = sel.normalized();
+            app.push_undo();
+            delete_selection(app.current_buffer_mut(), sx, sy, ex, ey);
+            app.selection = None;
+            app.current_buffer_mut().modified = true;
+            return;
+        }
+        if let KeyCode::Char(open) = code {
+            let close = editor::closing_pair(open)
+                .filter(|_| !sel.is_empty() && !flashes_as_unbound(code, modifiers));
+            if let Some(close) = close {
+                wrap_selection(app, open, close);
+                return;
+            }
+        }
+    }
+
+    match code {
+        KeyCode::Up if modifiers.contains(KeyModifiers::CONTROL) => {
+            let buf = app.current_buffer_mut();
+            let old_y = buf.cursor_y;
+            buf.cursor_y = 0;
+            buf.cursor_x = 0;
+            if config.trim_edited_lines {
+                app.trim_left_line(old_y);
+            }
+            update_viewport(app, config);
+            app.selection = None;
+        }
+        KeyCode::Down if modifiers.contains(KeyModifiers::CONTROL) => {
+            let buf = app.current_buffer_mut();
+            let old_y = buf.cursor_y;
+            buf.cursor_y = buf.lines.len().saturating_sub(1);
+            buf.cursor_x = buf.lines[buf.cursor_y].len();
+            if config.trim_edited_lines {
+                app.trim_left_line(old_y);
+            }
+            update_viewport(app, config);
+            app.selection = None;
+        }
+        KeyCode::Up | KeyCode::Down if modifiers.contains(KeyModifiers::ALT) => {
+            move_lines(app, code == KeyCode::Up);
+            update_viewport(app, config);
+        }
+        KeyCode::Left | KeyCode::Right if modifiers.contains(KeyModifiers::ALT) => {
+            let (term_w, term_h) = size().unwrap_or((80, 24));
+            let sidebar_width = app.current_buffer().gutter_width(config.show_line_numbers);
+            let view_width = term_w.saturating_sub(sidebar_width) as usize;
+            let view_height =
+                config.editor_height(term_h, config.tab_bar_visible(app.buffers.len()));
+            let delta = if code == KeyCode::Left {
+                -HSCROLL_STEP
+            } else {
+                HSCROLL_STEP
+            };
+            app.current_buffer_mut().scroll_horizontal(
+                delta,
+                view_width,
+                view_height,
+                config.tab_display_width,
+            );
+        }
+        KeyCode::Tab | KeyCode::BackTab
+            if code == KeyCode::BackTab
+                || app
+                    .selection
+                    .as_ref()
+                    .is_some_and(|sel| sel.start_y != sel.end_y) =>
+        {
+            shift_lines(app, config, code == KeyCode::BackTab);
+        }
+        KeyCode::Tab => {
+            let indent = config.indent_for_buffer(app.current_buffer()).unit();
+            replace_selection(app);
+            let buf = app.current_buffer_mut();
+            buf.lines[buf.cursor_y].insert_str(buf.cursor_x, &indent);
+            buf.cursor_x += indent.len();
+            buf.modified = true;
+            buf.edited_line = Some(buf.cursor_y);
+        }
+        KeyCode::Char(_) if flashes_as_unbound(code, modifiers) => {
+            let combo = KeyCombo { code, modifiers };
+            app.flash_status(format!("UNBOUND: {}", combo.label()));
+        }
+        KeyCode::Char(c) => {
+            replace_selection(app);
+            let buf = app.current_buffer_mut();
+            buf.lines[buf.cursor_y].insert(buf.cursor_x, c);
+            buf.cursor_x += c.len_utf8();
+            buf.modified = true;
+            buf.edited_line = Some(buf.cursor_y);
+            app.selection = None;
+        }
+        KeyCode::Enter => {
+            replace_selection(app);
+            let buf = app.current_buffer_mut();
+            let remaining = buf.lines[buf.cursor_y].split_off(buf.cursor_x);
+            buf.lines.insert(buf.cursor_y + 1, remaining);
+            buf.cursor_y += 1;
+            buf.cursor_x = 0;
+            buf.modified = true;
+            buf.edited_line = Some(buf.cursor_y);
+            app.selection = None;
+        }
+        KeyCode::Backspace => {
+            app.push_undo();
+            let tab_size = config.indent_for_buffer(app.current_buffer()).tab_size;
+            let buf = app.current_buffer_mut();
+            if buf.cursor_x > 0 && modifiers.contains(KeyModifiers::CONTROL) {
+                let start =
+                    editor::word_left(&buf.lines[buf.cursor_y], buf.cursor_x, &config.word_chars);
+                buf.lines[buf.cursor_y].replace_range(start..buf.cursor_x, "");
+                buf.cursor_x = start;
+            } else if buf.cursor_x > 0 {
+                let width =
+                    editor::backspace_width(&buf.lines[buf.cursor_y], buf.cursor_x, tab_size);
+                buf.cursor_x -= width;
+                buf.lines[buf.cursor_y].replace_range(buf.cursor_x..buf.cursor_x + width, "");
+            } else if buf.cursor_y > 0 {
+                let current_line = buf.lines.remove(buf.cursor_y);
+                buf.cursor_y -= 1;
+                buf.cursor_x = buf.lines[buf.cursor_y].len();
+                buf.lines[buf.cursor_y].push_str(&current_line);
+            }
+            buf.modified = true;
+            buf.edited_line = Some(buf.cursor_y);
+            app.selection = None;
+        }
+        KeyCode::Up
+        | KeyCode::Down
+        | KeyCode::Left
+        | KeyCode::Right
+        | KeyCode::Home
+        | KeyCode::End
+        | KeyCode::PageUp
+        | KeyCode::PageDown => {
+            let old_x = app.current_buffer().cursor_x;
+            let old_y = app.current_buffer().cursor_y;
+            let (_, term_h) = size().unwrap_or((80, 24));
+            let page = config
+                .editor_height(term_h, config.tab_bar_visible(app.buffers.len()))
+                .max(1);
+
+            if modifiers.contains(KeyModifiers::SHIFT) && app.selection.is_none() {
+                app.selection = Some(Selection::new(old_x, old_y));
+            }
+
+            {
+                let buf = app.current_buffer_mut();
+                match code {
+                    KeyCode::Up if buf.cursor_y > 0 => {
+                        buf.cursor_y -= 1;
+                        buf.cursor_x = editor::vertical_move_x(
+                            &buf.lines[old_y],
+                            old_x,
+                            &buf.lines[buf.cursor_y],
+                            config.tab_display_width,
+                        );
+                    }
+                    KeyCode::Down if buf.cursor_y < buf.lines.len() - 1 => {
+                        buf.cursor_y += 1;
+                        buf.cursor_x = editor::vertical_move_x(
+                            &buf.lines[old_y],
+                            old_x,
+                            &buf.lines[buf.cursor_y],
+                            config.tab_display_width,
+                        );
+                    }
+                    KeyCode::Left
+                        if buf.cursor_x > 0 && modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        buf.cursor_x = editor::word_left(
+                            &buf.lines[buf.cursor_y],
+                            buf.cursor_x,
+                            &config.word_chars,
+                        )
+                    }
+                    KeyCode::Right
+                        if buf.cursor_x < buf.lines[buf.cursor_y].len()
+                            && modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        buf.cursor_x = editor::word_right(
+                            &buf.lines[buf.cursor_y],
+                            buf.cursor_x,
+                            &config.word_chars,
+                        )
+                    }
+                    KeyCode::Left if buf.cursor_x > 0 => {
+                        buf.cursor_x = editor::prev_boundary(&buf.lines[buf.cursor_y], buf.cursor_x)
+                    }
+                    KeyCode::Right if buf.cursor_x < buf.lines[buf.cursor_y].len() => {
+                        buf.cursor_x = editor::next_boundary(&buf.lines[buf.cursor_y], buf.cursor_x)
+                    }
+                    KeyCode::Home => {
+                        buf.cursor_x = editor::smart_home(&buf.lines[buf.cursor_y], buf.cursor_x)
+                    }
+                    KeyCode::End => buf.cursor_x = buf.lines[buf.cursor_y].len(),
+                    KeyCode::PageUp | KeyCode::PageDown => {
+                        let last = buf.lines.len() - 1;
+                        if code == KeyCode::PageUp {
+                            buf.cursor_y = old_y.saturating_sub(page);
+                            buf.viewport_offset_y = buf.viewport_offset_y.saturating_sub(page);
+                        } else {
+                            buf.cursor_y = (old_y + page).min(last);
+                            buf.viewport_offset_y = (buf.viewport_offset_y + page).min(last);
+                        }
+                        buf.cursor_x = editor::vertical_move_x(
+                            &buf.lines[old_y],
+                            old_x,
+                            &buf.lines[buf.cursor_y],
+                            config.tab_display_width,
+                        );
+                    }
+                    _ => {}
+                }
+            }
+
+            update_viewport(app, config);
+
+            let new_x = app.current_buffer().cursor_x;
+            let new_y = app.current_buffer().cursor_y;
+
+            if modifiers.contains(KeyModifiers::SHIFT) {
+                if let Some(sel) = &mut app.selection {
+                    sel.end_x = new_x;
+                    sel.end_y = new_y;
+                }
+            } else {
+                app.selection = None;
+            }
+            if config.trim_edited_lines && app.selection.is_none() {
+                app.trim_left_line(old_y);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn flashes_as_unbound(code: KeyCode, modifiers: KeyModifiers) -> bool {
+    let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+    let alt = modifiers.contains(KeyModifiers::ALT);
+    matches!(code, KeyCode::Char(_)) && ctrl != alt
+}
+
+fn perform_keybind_action(
+    app: &mut AppState,
+    action: KeybindAction,
+    config: &Config,
+    mode: &mut Mode,
+) {
+    match action {
+        KeybindAction::Menu => {
+            app.selection = None;
+            *mode = Mode::Menu;
+        }
+        KeybindAction::Save => {
+            save_current_buffer(app, config);
+        }
+        KeybindAction::Undo => {
+            app.undo();
+        }
+        KeybindAction::Redo => {
+            app.redo();
+        }
+        KeybindAction::NewTab => app.open_new_buffer(config),
+        KeybindAction::CloseTab => {
+            if app.current_buffer().modified {
+                app.confirm_mode = Some(ConfirmType::CloseTab);
+                app.confirm_choice = config.confirm_close_default;
+                *mode = Mode::Confirm(ConfirmType::CloseTab);
+            } else {
+                close_current_tab(app);
+            }
+        }
+        KeybindAction::NextTab => {
+            app.next_buffer();
+            update_viewport(app, config);
+        }
+        KeybindAction::PrevTab => {
+            app.prev_buffer();
+            update_viewport(app, config);
+        }
+        KeybindAction::SelectAll => {
+            let buf = app.current_buffer();
+            if !buf.lines.is_empty() {
+                app.selection = Some(Selection {
+                    start_x: 0,
+                    start_y: 0,
+                    end_x: buf.lines[buf.lines.len() - 1].len(),
+                    end_y: buf.lines.len() - 1,
+                });
+            }
+        }
+        KeybindAction::Copy => {
+            if let Some(sel) = app.selection.as_ref() {
+                let (sx, sy, ex, ey) = sel.normalized();
+                app.clipboard = extract_selected_text(app.current_buffer(), sx, sy, ex, ey);
+                app.flash_status("COPIED".to_string());
+            }
+        }
+        KeybindAction::Cut => {
+            if let Some(sel) = app.selection.as_ref() {
+                let (sx, sy, ex, ey) = sel.normalized();
+                app.clipboard = extract_selected_text(app.current_buffer(), sx, sy, ex, ey);
+                app.push_undo();
+                delete_selection(app.current_buffer_mut(), sx, sy, ex, ey);
+                app.selection = None;
+                app.current_buffer_mut().modified = true;
+                app.flash_status("CUT".to_string());
+            }
+        }
+        KeybindAction::Paste => paste_clipboard(app, config),
+        KeybindAction::Find => {
+            app.input_mode = true;
+            app.prompt_type = PromptType::Find;
+            app.input_buffer.clear();
+        }
+        KeybindAction::GoToLine => {
+            app.input_mode = true;
+            app.prompt_type = PromptType::GoToLine;
+            app.input_buffer.clear();
+        }
+        KeybindAction::WipeBuffer => *mode = Mode::ConfirmWipe,
+        KeybindAction::RevealInExplorer => {
+            if change_explorer_dir(app, app.explorer_start_dir(), config) {
+                *mode = Mode::Explorer;
+            }
+        }
+        KeybindAction::DuplicateSelection => duplicate_selection(app),
+        KeybindAction::DuplicateLine => duplicate_line(app),
+        KeybindAction::BufferInfo => *mode = Mode::BufferInfo,
+        KeybindAction::FindNext => find_step(app, config, true),
+        KeybindAction::FindPrevious => find_step(app, config, false),
+        KeybindAction::ToggleBufferSyntax => {
+            let buf = app.current_buffer_mut();
+            let enabled = !buf.syntax_enabled(config.syntax_for_buffer(buf));
+            buf.syntax_override = Some(enabled);
+            let msg = format!(
+                "SYNTAX {} FOR {}",
+                if enabled { "ON" } else { "OFF" },
+                buf.filename
+            );
+            app.flash_status(msg);
+        }
+        KeybindAction::ResetToDefault => {}
+    }
+}
+
+fn extract_selected_text(buf: &Buffer, sx: usize, sy: usize, ex: usize, ey: usize) -> String {
+    let mut text = String::new();
+    for y in sy..=ey {
+        let line = &buf.lines[y];
+        let start = if y == sy { sx } else { 0 };
+        let end = if y == ey { ex } else { line.len() };
+        if start < end {
+            text.push_str(&line[start..end]);
+        }
+        if y < ey {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+fn insert_text(buf: &mut Buffer, text: &str) {
+    let tail = buf.lines[buf.cursor_y].split_off(buf.cursor_x);
+    for (i, part) in text.split('\n').enumerate() {
+        if i > 0 {
+            buf.cursor_y += 1;
+            buf.lines.insert(buf.cursor_y, String::new());
+            buf.cursor_x = 0;
+        }
+        buf.lines[buf.cursor_y].push_str(part);
+        buf.cursor_x += part.len();
+    }
+    buf.lines[buf.cursor_y].push_str(&tail);
+}
+
+pub fn handle_paste_event(app: &mut AppState, text: &str, mode: Mode) {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    if app.input_mode {
+        app.input_buffer.push_str(text.lines().next().unwrap_or(""));
+    } else if mode == Mode::Editing && !text.is_empty() {
+        replace_selection(app);
+        let buf = app.current_buffer_mut();
+        insert_text(buf, &text);
+        buf.modified = true;
+    }
+}
+
+fn paste_clipboard(app: &mut AppState, config: &Config) {
+    if app.clipboard.is_empty() {
+        return;
+    }
+    app.push_undo();
+    let paste_text = if config.reindent_paste {
+        let buf = app.current_buffer();
+        let line = &buf.lines[buf.cursor_y];
+        let indent = &line[..line.len() - line.trim_start().len()];
+        editor::reindent_block(&app.clipboard, indent)
+    } else {
+        app.clipboard.clone()
+    };
+    let buf = app.current_buffer_mut();
+    insert_text(buf, &paste_text);
+    buf.modified = true;
+    app.selection = None;
+}
+
+fn duplicate_selection(app: &mut AppState) {
+    let Some((sx, sy, ex, ey)) = app.selection.as_ref().map(|sel| sel.normalized()) else {
+        duplicate_line(app);
+        return;
+    };
+    app.push_undo();
+    let text = extract_selected_text(app.current_buffer(), sx, sy, ex, ey);
+    let buf = app.current_buffer_mut();
+    buf.cursor_x = ex;
+    buf.cursor_y = ey;
+    insert_text(buf, &text);
+    buf.modified = true;
+    let (end_x, end_y) = (buf.cursor_x, buf.cursor_y);
+    app.selection = Some(Selection {
+        start_x: ex,
+        start_y: ey,
+        end_x,
+        end_y,
+    });
+}
+
+fn shift_lines(app: &mut AppState, config: &Config, dedent: bool) {
+    let (first, last) = match app.selection.as_ref().map(|sel| sel.normalized()) {
+        Some((_, sy, _, ey)) => (sy, ey),
+        None => {
+            let y = app.current_buffer().cursor_y;
+            (y, y)
+        }
+    };
+    let indent = config.indent_for_buffer(app.current_buffer());
+    app.push_undo();
+    let buf = app.current_buffer_mut();
+    let shifts = if dedent {
+        editor::dedent_lines(&mut buf.lines[first..=last], indent.tab_size)
+    } else {
+        editor::indent_lines(&mut buf.lines[first..=last], &indent.unit())
+    };
+    if shifts.iter().all(|&shift| shift == 0) {
+        return;
+    }
+    let shift_x = |x: usize, y: usize| {
+        let shift = shifts[y - first];
+        if dedent {
+            x.saturating_sub(shift)
+        } else if x == 0 {
+            0
+        } else {
+            x + shift
+        }
+    };
+    buf.cursor_x = shift_x(buf.cursor_x, buf.cursor_y);
+    buf.modified = true;
+    if let Some(sel) = &mut app.selection {
+        sel.start_x = shift_x(sel.start_x, sel.start_y);
+        sel.end_x = shift_x(sel.end_x, sel.end_y);
+    }
+}
+
+fn replace_selection(app: &mut AppState) {
+    app.push_undo();
+    if let Some((sx, sy, ex, ey)) = app.selection.take().map(|sel| sel.normalized()) {
+        delete_selection(app.current_buffer_mut(), sx, sy, ex, ey);
+    }
+}
+
+fn wrap_selection(app: &mut AppState, open: char, close: char) {
+    let Some((sx, sy, ex, ey)) = app.selection.as_ref().map(|sel| sel.normalized()) else {
+        return;
+    };
+    app.push_undo();
+    let buf = app.current_buffer_mut();
+    buf.lines[ey].insert(ex, close);
+    buf.lines[sy].insert(sx, open);
+    let end_x = if sy == ey { ex + open.len_utf8() } else { ex };
+    buf.cursor_x = end_x;
+    buf.cursor_y = ey;
+    buf.modified = true;
+    app.selection = Some(Selection {
+        start_x: sx + open.len_utf8(),
+        start_y: sy,
+        end_x,
+        end_y: ey,
+    });
+}
+
+fn move_lines(app: &mut AppState, up: bool) {
+    let (first, last) = match app.selection.as_ref().map(|sel| sel.normalized()) {
+        Some((_, sy, _, ey)) => (sy, ey),
+        None => {
+            let y = app.current_buffer().cursor_y;
+            (y, y)
+        }
+    };
+    if (up && first == 0) || (!up && last + 1 >= app.current_buffer().lines.len()) {
+        return;
+    }
+    app.push_undo();
+    let buf = app.current_buffer_mut();
+    if up {
+        buf.lines[first - 1..=last].rotate_left(1);
+        buf.cursor_y -= 1;
+    } else {
+        buf.lines[first..=last + 1].rotate_right(1);
+        buf.cursor_y += 1;
+    }
+    buf.edited_line = buf.edited_line.map(|y| match y {
+        y if up && y + 1 == first => last,
+        y if !up && y == last + 1 => first,
+        y if (first..=last).contains(&y) && up => y - 1,
+        y if (first..=last).contains(&y) => y + 1,
+        y => y,
+    });
+    buf.modified = true;
+    if let Some(sel) = &mut app.selection {
+        if up {
+            sel.start_y -= 1;
+            sel.end_y -= 1;
+        } else {
+            sel.start_y += 1;
+            sel.end_y += 1;
+        }
+    }
+}
+
+fn duplicate_line(app: &mut AppState) {
+    let (first, last) = match app.selection.as_ref().map(|sel| sel.normalized()) {
+        Some((_, sy, _, ey)) if sy != ey => (sy, ey),
+        _ => {
+            let y = app.current_buffer().cursor_y;
+            (y, y)
+        }
+    };
+    app.push_undo();
+    let buf = app.current_buffer_mut();
+    let block = buf.lines[first..=last].to_vec();
+    let count = block.len();
+    buf.lines.splice(last + 1..last + 1, block);
+    buf.cursor_y += count;
+    buf.modified = true;
+    if let Some(sel) = &mut app.selection {
+        sel.start_y += count;
+        sel.end_y += count;
+    }
+}
+
+fn delete_selection(buf: &mut Buffer, sx: usize, sy: usize, ex: usize, ey: usize) {
+    if sy == ey {
+        buf.lines[sy].drain(sx..ex);
+        buf.cursor_x = sx;
+    } else {
+        let mut new_line = buf.lines[sy][..sx].to_string();
+        new_line.push_str(&buf.lines[ey][ex..]);
+        buf.lines.splice(sy..=ey, std::iter::once(new_line));
+        buf.cursor_y = sy;
+        buf.cursor_x = sx;
+    }
+}
+
+fn idle_tick(app: &mut AppState, config: &Config) -> bool {
+    let flash_cleared = app.tick_flash();
+    let explorer_loaded = load_more_explorer(app);
+    flash_cleared || explorer_loaded || tick_save_age(app, config)
+}
+
+fn tick_save_age(app: &mut AppState, config: &Config) -> bool {
+    if !config.show_status_bar {
+        return false;
+    }
+    let save_age = ui::format_save_age(app.current_buffer().last_saved, Local::now());
+    if save_age == app.shown_save_age {
+        return false;
+    }
+    app.shown_save_age = save_age;
+    true
+}
+
+pub fn finish_frame(
+    app: &mut AppState,
+    config: &Config,
+    needs_redraw: bool,
+    follow_cursor: bool,
+) -> bool {
+    let ticked = idle_tick(app, config);
+    if needs_redraw && follow_cursor {
+        update_viewport(app, config);
+    }
+    needs_redraw || ticked
+}
+
+pub fn update_viewport(app: &mut AppState, config: &Config) {
+    let (term_w, term_h) = size().unwrap_or((80, 24));
+    app.ensure_cursor_visible(term_w, term_h, config);
+}
+
+fn handle_prompt_input(app: &mut AppState, code: KeyCode, mode: &mut Mode, config: &mut Config) {
+    match code {
+        KeyCode::Esc => {
+            app.input_mode = false;
+            app.input_buffer.clear();
+            app.pending_replace = None;
+        }
+        KeyCode::Enter => {
+            let input = app.input_buffer.clone();
+            match app.prompt_type {
+                PromptType::SaveAs => {
+                    app.input_buffer.clear();
+                    app.input_mode = false;
+                    let buf = app.current_buffer_mut();
+                    buf.filename = input.clone();
+                    buf.file_path = None;
+                    save_current_buffer(app, config);
+                }
+                PromptType::Rename if !input.is_empty() => {
+                    let target = PathBuf::from(&input);
+                    if target.exists() {
+                        app.pending_rename = Some(target);
+                        *mode = Mode::Confirm(ConfirmType::Overwrite);
+                    } else {
+                        rename_current_file(app, target, config);
+                    }
+                }
+                PromptType::GoToLine => {
+                    let buf = app.current_buffer();
+                    if let Some(target) = parse_goto_target(&input, buf.cursor_y, buf.lines.len()) {
+                        let buf = app.current_buffer_mut();
+                        buf.cursor_y = target;
+                        buf.cursor_x = 0;
+                        update_viewport(app, config);
+                    }
+                }
+                PromptType::Find if !input.is_empty() => find_text(app, &input, config),
+                PromptType::AlignIndent => align_selection_indent(app, &input, config),
+                PromptType::FileType => set_filetype(app, &input, config),
+                PromptType::Replace | PromptType::ReplaceAll if !input.is_empty() => {
+                    let all = app.prompt_type == PromptType::ReplaceAll;
+                    app.pending_replace = Some((input.clone(), all));
+                    app.prompt_type = PromptType::ReplaceWith;
+                }
+                PromptType::ReplaceWith => {
+                    if let Some((query, all)) = app.pending_replace.take() {
+                        replace_text(app, &query, &input, all, config);
+                    }
+                }
+                PromptType::ExportTheme | PromptType::ImportTheme => {
+                    match config::get_theme_path(&input) {
+                        None => app.flash_status("INVALID THEME NAME".to_string()),
+                        Some(path) if app.prompt_type == PromptType::ExportTheme => {
+                            match config::export_theme(&app.current_palette.to_config(), &path) {
+                                Ok(()) => {
+                                    app.flash_status(format!("THEME EXPORTED: {}", path.display()))
+                                }
+                                Err(e) => app.flash_status(format!("EXPORT FAILED: {}", e)),
+                            }
+                        }
+                        Some(path) => match config::import_theme(&path) {
+                            Ok(palette) => {
+                                app.current_palette = Palette::from_config(&palette);
+                                config.palette = palette;
+                                let _ = config::save_config(config);
+                                app.populate_color_entries();
+                                app.flash_status("THEME IMPORTED".to_string());
+                            }
+                            Err(e) => app.flash_status(format!("IMPORT FAILED: {}", e)),
+                        },
+                    }
+                }
+                _ => {}
+            }
+            app.input_mode = app.pending_replace.is_some();
+            app.input_buffer.clear();
+        }
+        KeyCode::Char(c) => app.input_buffer.push(c),
+        KeyCode::Backspace => {
+            app.input_buffer.pop();
+        }
+        _ => {}
+    }
+}
+
+pub fn open_file(app: &mut AppState, path: PathBuf, config: &Config) -> std::io::Result<()> {
+    let (mut lines, line_ending) = editor::load_with_line_ending(&path.to_string_lossy())?;
+    if config.ensure_final_newline {
+        editor::trim_final_newlines(&mut lines);
+    }
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+    let mut new_buffer = Buffer::new(name);
+    new_buffer.indent_style = editor::detect_indent(&lines);
+    new_buffer.lines = lines;
+    new_buffer.line_ending = line_ending;
+    new_buffer.last_saved = editor::modified_time(&path);
+    new_buffer.file_path = Some(path);
+    app.add_buffer(new_buffer);
+    Ok(())
+}
+
+pub fn open_startup_arg(app: &mut AppState, arg: &str, config: &Config) -> Option<Mode> {
+    if Path::new(arg).is_dir() {
+        let dir = fs::canonicalize(arg).unwrap_or_else(|_| PathBuf::from(arg));
+        return change_explorer_dir(app, dir, config).then_some(Mode::Explorer);
+    }
+    let (path, line, column) = parse_file_arg(arg);
+    match open_file(app, path.clone(), config) {
+        Ok(()) => {
+            if let Some(line) = line {
+                jump_to(app, line, column.unwrap_or(1), config);
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            open_new_file(app, path);
+            app.flash_status("NEW FILE".to_string());
+        }
+        Err(e) => app.flash_status(format!("OPEN FAILED: {}", e)),
+    }
+    None
+}
+
+fn open_new_file(app: &mut AppState, path: PathBuf) {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+    let mut new_buffer = Buffer::new(name);
+    new_buffer.file_path = Some(path);
+    app.add_buffer(new_buffer);
+}
+
+pub fn parse_file_arg(arg: &str) -> (PathBuf, Option<usize>, Option<usize>) {
+    let name_start = arg.rfind(['/', '\\']).map_or(0, |i| i + 1);
+    let (path, line, column) = match split_position(arg, name_start) {
+        Some((rest, last)) => match split_position(rest, name_start) {
+            Some((path, line)) => (path, line, Some(last)),
+            None => (rest, last, None),
+        },
+        None => return (PathBuf::from(arg), None, None),
+    };
+    if line == 0 || column == Some(0) {
+        return (PathBuf::from(arg), None, None);
+    }
+    (PathBuf::from(path), Some(line), column)
+}
+
+fn split_position(text: &str, name_start: usize) -> Option<(&str, usize)> {
+    let (rest, part) = text.rsplit_once(':')?;
+    let n = part.parse::<usize>().ok()?;
+    (rest.len() >= name_start).then_some((rest, n))
+}
+
+pub fn jump_to(app: &mut AppState, line: usize, column: usize, config: &Config) {
+    let (_, term_h) = size().unwrap_or((80, 24));
+    let buf = app.current_buffer_mut();
+    buf.cursor_y = line
+        .saturating_sub(1)
+        .min(buf.lines.len().saturating_sub(1));
+    let text = &buf.lines[buf.cursor_y];
+    buf.cursor_x = text
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map_or(text.len(), |(i, _)| i);
+    buf.viewport_offset_y = buf.cursor_y.saturating_sub(term_h as usize / 2);
+    update_viewport(app, config);
+}
+
+fn refresh_find_cache(app: &mut AppState, query: &str) -> bool {
+    if app
+        .find_cache
+        .as_ref()
+        .is_none_or(|cache| cache.query != query)
+    {
+        let found = editor::find_matches(&app.current_buffer().lines, query, || {
+            poll(Duration::ZERO).unwrap_or(false)
+        });
+        let Some(matches) = found else {
+            app.flash_status("SEARCH CANCELLED".to_string());
+            return false;
+        };
+        app.find_cache = Some(FindCache {
+            query: query.to_string(),
+            matches,
+        });
+    }
+    true
+}
+
+fn find_text(app: &mut AppState, query: &str, config: &Config) {
+    app.last_search = Some(query.to_string());
+    if !refresh_find_cache(app, query) {
+        return;
+    }
+
+    let cursor_y = app.current_buffer().cursor_y;
+    let hit = app.find_cache.as_ref().and_then(|cache| {
+        let idx = cache.matches.partition_point(|&(y, _)| y < cursor_y);
+        cache.matches.get(idx).copied()
+    });
+    if let Some((y, x)) = hit {
+        let buf = app.current_buffer_mut();
+        buf.cursor_y = y;
+        buf.cursor_x = x;
+        update_viewport(app, config);
+    }
+}
+
+fn replace_text(app: &mut AppState, query: &str, replacement: &str, all: bool, config: &Config) {
+    if all {
+        if editor::count_matches(&app.current_buffer().lines, query) == 0 {
+            app.flash_status(format!("NOT FOUND: {}", query));
+            return;
+        }
+        app.push_undo();
+        let buf = app.current_buffer_mut();
+        let count = editor::replace_all(&mut buf.lines, query, replacement);
+        buf.cursor_x = editor::clamp_to_boundary(&buf.lines[buf.cursor_y], buf.cursor_x);
+        buf.modified = true;
+        app.selection = None;
+        app.flash_status(format!("REPLACED {}", count));
+        return;
+    }
+
+    let buf = app.current_buffer();
+    let Some((y, x)) = editor::next_match(&buf.lines, query, (buf.cursor_y, buf.cursor_x)) else {
+        app.flash_status(format!("NOT FOUND: {}", query));
+        return;
+    };
+    app.push_undo();
+    let buf = app.current_buffer_mut();
+    buf.lines[y].replace_range(x..x + query.len(), replacement);
+    buf.cursor_y = y;
+    buf.cursor_x = x + replacement.len();
+    buf.modified = true;
+    app.selection = None;
+    update_viewport(app, config);
+    app.flash_status("REPLACED 1".to_string());
+}
+
+fn find_step(app: &mut AppState, config: &Config, forward: bool) {
+    let Some(query) = app.last_search.clone() else {
+        app.flash_status("NO PREVIOUS SEARCH".to_string());
+        return;
+    };
+    if !refresh_find_cache(app, &query) {
+        return;
+    }
+
+    let buf = app.current_buffer();
+    let cursor = (buf.cursor_y, buf.cursor_x);
+    let hit = app.find_cache.as_ref().and_then(|cache| {
+        let matches = &cache.matches;
+        if forward {
+            let idx = matches.partition_point(|&m| m <= cursor);
+            matches.get(idx).or(matches.first()).copied()
+        } else {
+            let idx = matches.partition_point(|&m| m < cursor);
+            idx.checked_sub(1)
+                .and_then(|i| matches.get(i))
+                .or(matches.last())
+                .copied()
+        }
+    });
+    match hit {
+        Some((y, x)) => {
+            let buf = app.current_buffer_mut();
+            buf.cursor_y = y;
+            buf.cursor_x = x;
+            app.selection = None;
+            update_viewport(app, config);
+        }
+        None => app.flash_status(format!("NOT FOUND: {}", query)),
+    }
+}
+
+fn parse_goto_target(input: &str, current_y: usize, line_count: usize) -> Option<usize> {
+    let input = input.trim();
+    let last = line_count.saturating_sub(1);
+
+    let target = if let Some(rest) = input.strip_prefix('+') {
+        current_y.saturating_add(rest.parse::<usize>().ok()?)
+    } else if let Some(rest) = input.strip_prefix('-') {
+        current_y.saturating_sub(rest.parse::<usize>().ok()?)
+    } else {
+        input.parse::<usize>().ok()?.saturating_sub(1)
+    };
+
+    Some(target.min(last))
+}
+
+fn refresh_explorer(app: &mut AppState, config: &Config) {
+    let dir = app.current_dir.clone();
+    change_explorer_dir(app, dir, config);
+}
+
+pub fn change_explorer_dir(app: &mut AppState, dir: PathBuf, config: &Config) -> bool {
+    match fs::read_dir(&dir) {
+        Ok(entries) => {
+            app.current_dir = dir;
+            app.explorer_files.clear();
+            app.explorer_pending = Some(entries);
+            app.explorer_idx = 0;
+            app.explorer_offset = 0;
+            load_more_explorer(app);
+            update_explorer_preview(app, config);
+            true
+        }
+        Err(e) => {
+            app.flash_status(editor::describe_io_error(&e));
+            false
+        }
+    }
+}
+
+pub fn load_more_explorer(app: &mut AppState) -> bool {
+    let Some(entries) = app.explorer_pending.as_mut() else {
+        return false;
+    };
+
+    let mut read = 0;
+    let mut batch = Vec::new();
+    for entry in entries.by_ref().take(EXPLORER_BATCH) {
+        read += 1;
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        if entry.path().is_dir() {
+            batch.push(format!("{}{}", DIR_MARKER, name));
+        } else {
+            batch.push(format!("{}{}", FILE_MARKER, name));
+        }
+    }
+    if read < EXPLORER_BATCH {
+        app.explorer_pending = None;
+    }
+
+    let selected = app.explorer_files.get(app.explorer_idx).cloned();
+    batch.sort_by_cached_key(|name| explorer_sort_key(name));
+    // Earlier batches are already sorted, so only look up where each new
+    // entry goes and merge, keeping equal names in load order.
+    let positions: Vec<usize> = batch
+        .iter()
+        .map(|name| {
+            let key = explorer_sort_key(name);
+            app.explorer_files
+                .partition_point(|f| explorer_sort_key(f) <= key)
+        })
+        .collect();
+    let mut loaded = std::mem::take(&mut app.explorer_files).into_iter();
+    let mut merged = Vec::with_capacity(loaded.len() + batch.len());
+    let mut taken = 0;
+    for (name, pos) in batch.into_iter().zip(positions) {
+        merged.extend(loaded.by_ref().take(pos - taken));
+        merged.push(name);
+        taken = pos;
+    }
+    merged.extend(loaded);
+    app.explorer_files = merged;
+    if let Some(idx) = selected.and_then(|name| app.explorer_files.iter().position(|f| *f == name))
+    {
+        app.explorer_idx = idx;
+        app.explorer_offset = app.explorer_offset.min(idx);
+    }
+    true
+}
+
+fn explorer_sort_key(name: &str) -> (bool, String) {
+    (!name.starts_with(DIR_MARKER), name.to_lowercase())
+}
+
+fn update_explorer_preview(app: &mut AppState, config: &Config) {
+    app.explorer_preview.clear();
+    if !config.explorer_preview {
+        return;
+    }
+    let Some(selected) = app.explorer_files.get(app.explorer_idx) else {
+        return;
+    };
+    let Some(name) = selected.strip_prefix(FILE_MARKER) else {
+        return;
+    };
+    let (_, term_h) = size().unwrap_or((80, 24));
+    let path = app.current_dir.join(name);
+    app.explorer_preview = match editor::read_preview(&path, term_h as usize) {
+        Ok(lines) => lines,
+        Err(e) => vec![format!(
+            "({})",
+            editor::describe_io_error(&e).to_lowercase()
+        )],
+    };
+}
+
+fn handle_menu_selection(
+    tab: MenuTab,
+    idx: usize,
+    config: &mut Config,
+    app: &mut AppState,
+) -> std::io::Result<(bool, Mode)> {
+    match tab {
+        MenuTab::Re => match idx % 4 {
+            0 => Ok((false, Mode::Settings)),
+            1 => {
+                app.help_offset = 0;
+                Ok((false, Mode::Help))
+            }
+            2 => Ok((true, Mode::Editing)),
+            3 => {
+                let _ = config::save_config(config);
+                Ok((true, Mode::Editing))
+            }
+            _ => Ok((false, Mode::Editing)),
+        },
+        MenuTab::File => match idx % 7 {
+            0 => {
+                app.open_new_buffer(config);
+                Ok((false, Mode::Editing))
+            }
+            1 => Ok((false, Mode::Explorer)),
+            2 => {
+                if app.current_buffer().modified {
+                    app.confirm_mode = Some(ConfirmType::CloseTab);
+                    app.confirm_choice = config.confirm_close_default;
+                    Ok((false, Mode::Confirm(ConfirmType::CloseTab)))
+                } else {
+                    close_current_tab(app);
+                    Ok((false, Mode::Editing))
+                }
+            }
+            3 => {
+                app.next_buffer();
+                update_viewport(app, config);
+                Ok((false, Mode::Editing))
+            }
+            4 => {
+                app.prev_buffer();
+                update_viewport(app, config);
+                Ok((false, Mode::Editing))
+            }
+            5 => {
+                let buf = app.current_buffer();
+                app.input_buffer = format!("./{}", buf.filename);
+                app.prompt_type = PromptType::SaveAs;
+                app.input_mode = true;
+
+                Ok((false, Mode::Editing))
+            }
+            6 => {
+                app.input_buffer = app.current_buffer().save_path();
+                app.prompt_type = PromptType::Rename;
+                app.input_mode = true;
+                Ok((false, Mode::Editing))
+            }
+            _ => Ok((false, Mode::Editing)),
+        },
+        MenuTab::Edit => match idx % 10 {
+            0 => {
+                app.input_mode = true;
+                app.prompt_type = PromptType::Find;
+                app.input_buffer.clear();
+                Ok((false, Mode::Editing))
+            }
+            1 => {
+                app.input_mode = true;
+                app.prompt_type = PromptType::Replace;
+                app.input_buffer.clear();
+                Ok((false, Mode::Editing))
+            }
+            2 => {
+                app.input_mode = true;
+                app.prompt_type = PromptType::ReplaceAll;
+                app.input_buffer.clear();
+                Ok((false, Mode::Editing))
+            }
+            3 => {
+                app.input_mode = true;
+                app.prompt_type = PromptType::GoToLine;
+                app.input_buffer.clear();
+                Ok((false, Mode::Editing))
+            }
+            4 => Ok((false, Mode::ConfirmWipe)),
+            5 => {
+                convert_indentation(app, config, true);
+                Ok((false, Mode::Editing))
+            }
+            6 => {
+                convert_indentation(app, config, false);
+                Ok((false, Mode::Editing))
+            }
+            7 => Ok((false, open_outline(app, config))),
+            8 => {
+                duplicate_selection(app);
+                Ok((false, Mode::Editing))
+            }
+            9 => {
+                app.input_mode = true;
+                app.prompt_type = PromptType::AlignIndent;
+                app.input_buffer.clear();
+                Ok((false, Mode::Editing))
+            }
+            _ => Ok((false, Mode::Editing)),
+        },
+        MenuTab::View if idx % 6 == 5 => {
+            app.input_mode = true;
+            app.prompt_type = PromptType::FileType;
+            app.input_buffer = app
+                .current_buffer()
+                .filetype_override
+                .clone()
+                .unwrap_or_default();
+            Ok((false, Mode::Editing))
+        }
+        MenuTab::View => {
+            let msg = match idx % 6 {
+                0 => {
+                    config.show_header = !config.show_header;
+                    format!("HEADER {}", shown_hidden(config.show_header))
+                }
+                1 => {
+                    config.show_status_bar = !config.show_status_bar;
+                    format!("STATUS BAR {}", shown_hidden(config.show_status_bar))
+                }
+                2 => {
+                    config.show_line_numbers = !config.show_line_numbers;
+                    format!("LINE NUMBERS {}", shown_hidden(config.show_line_numbers))
+                }
+                3 => {
+                    config.show_tab_bar = !config.show_tab_bar;
+                    format!("TAB BAR {}", shown_hidden(config.show_tab_bar))
+                }
+                _ => {
+                    config.syntax_highlight = !config.syntax_highlight;
+                    format!(
+                        "SYNTAX {}",
+                        if config.syntax_highlight {
+                            "ENABLED"
+                        } else {
+                            "DISABLED"
+                        }
+                    )
+                }
+            };
+            let _ = config::save_config(config);
+            app.flash_status(msg);
+            Ok((false, Mode::Editing))
+        }
+    }
+}
+
+fn set_filetype(app: &mut AppState, input: &str, config: &Config) {
+    let filetype = input.trim().trim_start_matches('.').to_lowercase();
+    let buf = app.current_buffer_mut();
+    buf.filetype_override = (!filetype.is_empty()).then_some(filetype);
+    let msg = format!("FILE TYPE: {}", config.filetype_for(buf).to_uppercase());
+    app.flash_status(msg);
+}
+
+fn open_outline(app: &mut AppState, config: &Config) -> Mode {
+    let buf = app.current_buffer();
+    app.outline = editor::extract_symbols(&buf.lines, config.filetype_for(buf));
+    if app.outline.is_empty() {
+        app.flash_status("NO SYMBOLS".to_string());
+        return Mode::Editing;
+    }
+    let cursor_y = app.current_buffer().cursor_y;
+    app.outline_idx = app
+        .outline
+        .iter()
+        .rposition(|symbol| symbol.line <= cursor_y)
+        .unwrap_or(0);
+    app.outline_offset = (app.outline_idx + 1).saturating_sub(OUTLINE_ROWS);
+    Mode::Outline
+}
+
+fn shown_hidden(shown: bool) -> &'static str {
+    if shown {
+        "SHOWN"
+    } else {
+        "HIDDEN"
+    }
+}
+
+fn convert_indentation(app: &mut AppState, config: &Config, to_spaces: bool) {
+    let tab_size = config.indent_for_buffer(app.current_buffer()).tab_size;
+    app.push_undo();
+    let buf = app.current_buffer_mut();
+    let changed = if to_spaces {
+        buf.indent_style = Some(IndentStyle::Spaces(tab_size));
+        editor::tabs_to_spaces(&mut buf.lines, tab_size)
+    } else {
+        buf.indent_style = Some(IndentStyle::Tabs);
+        editor::spaces_to_tabs(&mut buf.lines, tab_size)
+    };
+    buf.cursor_x = editor::clamp_to_boundary(&buf.lines[buf.cursor_y], buf.cursor_x);
+    if changed > 0 {
+        buf.modified = true;
+    }
+    app.flash_status(format!("{} LINES CONVERTED", changed));
+}
+
+fn align_selection_indent(app: &mut AppState, input: &str, config: &Config) {
+    let indent = if input.trim().is_empty() {
+        None
+    } else if let Ok(width) = input.trim().parse::<usize>() {
+        let indent = config.indent_for_buffer(app.current_buffer());
+        Some(if indent.use_hard_tabs {
+            let tab_size = indent.tab_size.max(1);
+            format!(
+                "{}{}",
+                "\t".repeat(width / tab_size),
+                " ".repeat(width % tab_size)
+            )
+        } else {
+            " ".repeat(width)
+        })
+    } else {
+        app.flash_status("INVALID INDENT".to_string());
+        return;
+    };
+    let (start, end) = match app.selection.as_ref().map(|sel| sel.normalized()) {
+        Some((_, sy, _, ey)) => (sy, ey),
+        None => {
+            let y = app.current_buffer().cursor_y;
+            (y, y)
+        }
+    };
+    app.push_undo();
+    let buf = app.current_buffer_mut();
+    let changed = editor::align_indent(&mut buf.lines[start..=end], indent.as_deref());
+    buf.cursor_x = editor::clamp_to_boundary(&buf.lines[buf.cursor_y], buf.cursor_x);
+    if changed > 0 {
+        buf.modified = true;
+    }
+    app.selection = None;
+    app.flash_status(format!("{} LINES ALIGNED", changed));
+}
+
+fn next_tab(t: MenuTab) -> MenuTab {
+    match t {
+        MenuTab::Re => MenuTab::File,
+        MenuTab::File => MenuTab::Edit,
+        MenuTab::Edit => MenuTab::View,
+        MenuTab::View => MenuTab::Re,
+    }
+}
+
+fn prev_tab(t: MenuTab) -> MenuTab {
+    match t {
+        MenuTab::Re => MenuTab::View,
+        MenuTab::View => MenuTab::Edit,
+        MenuTab::Edit => MenuTab::File,
+        MenuTab::File => MenuTab::Re,
+    }
+}
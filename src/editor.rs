@@ -1,19 +1,220 @@
-use std::fs::{read_to_string, rename, File};
-use std::io::{BufWriter, Write};
+use crate::state::{IndentStyle, PreviewSkip, Symbol};
+use chrono::{DateTime, Local};
+use std::fs::{self, read_to_string, rename, File};
+use std::io::{self, BufWriter, ErrorKind, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "grapheme")]
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
 
 pub fn load_from_file(filename: &str) -> std::io::Result<Vec<String>> {
+    load_with_line_ending(filename).map(|(lines, _)| lines)
+}
+
+pub fn load_with_line_ending(filename: &str) -> std::io::Result<(Vec<String>, &'static str)> {
     let content = read_to_string(filename)?;
-    let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+    let line_ending = if content.contains("\r\n") {
+        "CRLF"
+    } else {
+        "LF"
+    };
+    let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
 
     if lines.is_empty() {
-        Ok(vec![String::new()])
+        lines.push(String::new());
+    }
+    Ok((lines, line_ending))
+}
+
+pub fn trim_final_newlines(lines: &mut Vec<String>) {
+    while lines.len() > 1 && lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+}
+
+pub const FIND_CANCEL_INTERVAL: usize = 4096;
+
+pub fn find_matches(
+    lines: &[String],
+    query: &str,
+    mut cancelled: impl FnMut() -> bool,
+) -> Option<Vec<(usize, usize)>> {
+    let mut matches = Vec::new();
+    for (y, line) in lines.iter().enumerate() {
+        if y > 0 && y % FIND_CANCEL_INTERVAL == 0 && cancelled() {
+            return None;
+        }
+        matches.extend(line.match_indices(query).map(|(x, _)| (y, x)));
+    }
+    Some(matches)
+}
+
+pub fn next_match(lines: &[String], query: &str, from: (usize, usize)) -> Option<(usize, usize)> {
+    let (y, x) = from;
+    if let Some(i) = lines.get(y).and_then(|line| line[x..].find(query)) {
+        return Some((y, x + i));
+    }
+    (y + 1..lines.len())
+        .chain(0..=y.min(lines.len().saturating_sub(1)))
+        .find_map(|i| lines[i].find(query).map(|x| (i, x)))
+}
+
+pub fn count_matches(lines: &[String], query: &str) -> usize {
+    lines.iter().map(|line| line.matches(query).count()).sum()
+}
+
+pub fn replace_all(lines: &mut [String], query: &str, replacement: &str) -> usize {
+    let mut count = 0;
+    for line in lines.iter_mut() {
+        let found = line.matches(query).count();
+        if found > 0 {
+            *line = line.replace(query, replacement);
+            count += found;
+        }
+    }
+    count
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MarkdownLine {
+    Text,
+    Heading,
+    Code,
+}
+
+pub fn closing_pair(open: char) -> Option<char> {
+    match open {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        '"' | '\'' | '`' => Some(open),
+        _ => None,
+    }
+}
+
+pub fn is_code_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+pub fn is_markdown_heading(line: &str) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return false;
+    }
+    let level = trimmed.bytes().take_while(|&b| b == b'#').count();
+    (1..=6).contains(&level)
+        && trimmed[level..]
+            .chars()
+            .next()
+            .is_none_or(|c| c == ' ' || c == '\t')
+}
+
+pub fn markdown_lines(lines: &[String], range: Range<usize>) -> Vec<MarkdownLine> {
+    let start = range.start.min(lines.len());
+    let end = range.end.min(lines.len());
+    let mut in_fence = lines[..start].iter().filter(|l| is_code_fence(l)).count() % 2 == 1;
+    lines[start..end]
+        .iter()
+        .map(|line| {
+            if is_code_fence(line) {
+                in_fence = !in_fence;
+                MarkdownLine::Code
+            } else if in_fence {
+                MarkdownLine::Code
+            } else if is_markdown_heading(line) {
+                MarkdownLine::Heading
+            } else {
+                MarkdownLine::Text
+            }
+        })
+        .collect()
+}
+
+pub fn inline_code_spans(line: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut rest = 0;
+    while let Some(open) = line[rest..].find('`').map(|i| rest + i) {
+        let Some(close) = line[open + 1..].find('`').map(|i| open + 1 + i) else {
+            break;
+        };
+        spans.push((open, close + 1));
+        rest = close + 1;
+    }
+    spans
+}
+
+pub const PREVIEW_MAX_BYTES: u64 = 4 * 1024 * 1024;
+pub const PREVIEW_READ_BYTES: u64 = 16 * 1024;
+
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.contains(&0)
+}
+
+pub fn preview_skip(size: u64, head: &[u8]) -> Option<PreviewSkip> {
+    if size > PREVIEW_MAX_BYTES {
+        Some(PreviewSkip::Large)
+    } else if is_binary(head) {
+        Some(PreviewSkip::Binary)
     } else {
-        Ok(lines)
+        None
     }
 }
 
+pub fn truncate_preview(content: &str, max_lines: usize) -> Vec<String> {
+    content
+        .lines()
+        .take(max_lines)
+        .map(|line| expand_tabs(line, 4))
+        .collect()
+}
+
+pub fn read_preview(path: &Path, max_lines: usize) -> io::Result<Vec<String>> {
+    let size = fs::metadata(path)?.len();
+    let mut head = Vec::new();
+    File::open(path)?
+        .take(PREVIEW_READ_BYTES)
+        .read_to_end(&mut head)?;
+
+    Ok(match preview_skip(size, &head) {
+        Some(PreviewSkip::Large) => vec!["(large)".to_string()],
+        Some(PreviewSkip::Binary) => vec!["(binary)".to_string()],
+        None => truncate_preview(&String::from_utf8_lossy(&head), max_lines),
+    })
+}
+
+pub fn modified_time(path: &Path) -> Option<DateTime<Local>> {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .map(DateTime::from)
+}
+
+pub fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path)
+        .map(|meta| meta.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
 pub fn save_to_file(lines: &Vec<String>, filename: &str) -> std::io::Result<()> {
-    let temp_name = format!("{}.tmp", filename);
+    let mut target = PathBuf::from(filename);
+    if is_symlink(&target) {
+        target = fs::canonicalize(&target).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => io::Error::new(ErrorKind::InvalidInput, "BROKEN SYMLINK"),
+            _ => e,
+        })?;
+    }
+    if let Ok(meta) = fs::metadata(&target) {
+        if !meta.is_file() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "NOT A REGULAR FILE",
+            ));
+        }
+    }
+
+    let temp_name = format!("{}.tmp", target.display());
     let file = File::create(&temp_name)?;
     let mut writer = BufWriter::new(file);
 
@@ -23,7 +224,397 @@ pub fn save_to_file(lines: &Vec<String>, filename: &str) -> std::io::Result<()>
     }
     writer.flush()?; 
 
-    rename(temp_name, filename)?;
+    rename(temp_name, target)?;
 
     Ok(())
 }
+
+pub fn describe_io_error(e: &io::Error) -> String {
+    match e.kind() {
+        ErrorKind::PermissionDenied => "PERMISSION DENIED".to_string(),
+        ErrorKind::NotFound => "DIRECTORY MISSING".to_string(),
+        ErrorKind::StorageFull => "DISK FULL".to_string(),
+        ErrorKind::IsADirectory => "IS A DIRECTORY".to_string(),
+        _ => e.to_string(),
+    }
+}
+
+pub fn detect_indent(lines: &[String]) -> Option<IndentStyle> {
+    let mut tab_lines = 0;
+    let mut space_lines = 0;
+    let mut deltas = [0usize; 9];
+    let mut prev_width = 0;
+
+    for line in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line.starts_with('\t') {
+            tab_lines += 1;
+            continue;
+        }
+
+        let width = line.len() - line.trim_start_matches(' ').len();
+        if width > 0 {
+            space_lines += 1;
+        }
+        if width > prev_width && width - prev_width < deltas.len() {
+            deltas[width - prev_width] += 1;
+        }
+        prev_width = width;
+    }
+
+    if tab_lines == 0 && space_lines == 0 {
+        return None;
+    }
+    if tab_lines > space_lines {
+        return Some(IndentStyle::Tabs);
+    }
+
+    (2..deltas.len())
+        .rev()
+        .max_by_key(|&w| deltas[w])
+        .filter(|&w| deltas[w] > 0)
+        .map(IndentStyle::Spaces)
+}
+
+const DECLARATION_MODIFIERS: [&str; 5] = ["pub", "async", "unsafe", "const", "extern"];
+
+fn declaration_keywords(file_ext: &str) -> &'static [&'static str] {
+    match file_ext {
+        "rs" => &[
+            "fn",
+            "struct",
+            "enum",
+            "trait",
+            "impl",
+            "mod",
+            "macro_rules!",
+        ],
+        "py" => &["def", "class"],
+        _ => &[],
+    }
+}
+
+pub fn extract_symbols(lines: &[String], file_ext: &str) -> Vec<Symbol> {
+    let keywords = declaration_keywords(file_ext);
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.starts_with(char::is_whitespace))
+        .filter_map(|(line_idx, line)| {
+            let mut words = line
+                .split_whitespace()
+                .skip_while(|w| DECLARATION_MODIFIERS.contains(w) || w.starts_with("pub("));
+            let first = words.next()?;
+            let base = first.split('<').next().unwrap_or(first);
+            let kind = keywords.iter().copied().find(|&k| k == base)?;
+            let rest = words.collect::<Vec<_>>().join(" ");
+            let name = if kind == "impl" {
+                rest.trim_end_matches('{').trim()
+            } else {
+                rest.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .next()
+                    .unwrap_or("")
+            };
+            (!name.is_empty()).then(|| Symbol {
+                line: line_idx,
+                kind,
+                name: name.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn leading_width(line: &str, tab_size: usize) -> (usize, usize) {
+    let mut width = 0;
+    let mut bytes = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += tab_size - width % tab_size,
+            _ => break,
+        }
+        bytes += 1;
+    }
+    (width, bytes)
+}
+
+#[cfg(feature = "grapheme")]
+pub fn prev_boundary(line: &str, x: usize) -> usize {
+    line[..x]
+        .grapheme_indices(true)
+        .next_back()
+        .map_or(0, |(i, _)| i)
+}
+
+#[cfg(feature = "grapheme")]
+pub fn next_boundary(line: &str, x: usize) -> usize {
+    line[x..].graphemes(true).next().map_or(x, |g| x + g.len())
+}
+
+#[cfg(not(feature = "grapheme"))]
+pub fn prev_boundary(line: &str, x: usize) -> usize {
+    line[..x]
+        .chars()
+        .next_back()
+        .map_or(0, |c| x - c.len_utf8())
+}
+
+#[cfg(not(feature = "grapheme"))]
+pub fn next_boundary(line: &str, x: usize) -> usize {
+    line[x..].chars().next().map_or(x, |c| x + c.len_utf8())
+}
+
+pub fn backspace_width(line: &str, x: usize, tab_size: usize) -> usize {
+    let indent = &line[..x];
+    if x == 0 || tab_size == 0 || !indent.bytes().all(|b| b == b' ') {
+        return x - prev_boundary(line, x);
+    }
+    (x - 1) % tab_size + 1
+}
+
+pub fn reindent_block(text: &str, indent: &str) -> String {
+    let indent_len = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let common = text
+        .split('\n')
+        .filter(|line| !line.trim().is_empty())
+        .map(indent_len)
+        .min()
+        .unwrap_or(0);
+
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            let stripped = &line[common.min(indent_len(line))..];
+            if i == 0 || stripped.is_empty() {
+                stripped.to_string()
+            } else {
+                format!("{}{}", indent, stripped)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn align_indent(lines: &mut [String], indent: Option<&str>) -> usize {
+    let indent_len = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let target = match indent {
+        Some(indent) => indent.to_string(),
+        None => lines
+            .first()
+            .map_or(String::new(), |line| line[..indent_len(line)].to_string()),
+    };
+    let mut changed = 0;
+    for line in lines.iter_mut() {
+        let bytes = indent_len(line);
+        if bytes < line.len() && line[..bytes] != target {
+            line.replace_range(..bytes, &target);
+            changed += 1;
+        }
+    }
+    changed
+}
+
+pub fn indent_lines(lines: &mut [String], unit: &str) -> Vec<usize> {
+    lines
+        .iter_mut()
+        .map(|line| {
+            if line.is_empty() {
+                return 0;
+            }
+            line.insert_str(0, unit);
+            unit.len()
+        })
+        .collect()
+}
+
+pub fn dedent_lines(lines: &mut [String], tab_size: usize) -> Vec<usize> {
+    lines
+        .iter_mut()
+        .map(|line| {
+            let removed = if line.starts_with('\t') {
+                1
+            } else {
+                line.len() - line.trim_start_matches(' ').len()
+            };
+            let removed = removed.min(tab_size.max(1));
+            line.replace_range(..removed, "");
+            removed
+        })
+        .collect()
+}
+
+pub fn is_word_char(c: char, extra: &str) -> bool {
+    c.is_alphanumeric() || c == '_' || extra.contains(c)
+}
+
+pub fn word_start(line: &str, x: usize, extra: &str) -> usize {
+    line[..x]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_word_char(c, extra))
+        .last()
+        .map_or(x, |(i, _)| i)
+}
+
+pub fn word_end(line: &str, x: usize, extra: &str) -> usize {
+    line[x..]
+        .find(|c: char| !is_word_char(c, extra))
+        .map_or(line.len(), |i| x + i)
+}
+
+pub fn word_left(line: &str, x: usize, extra: &str) -> usize {
+    let x = line[..x]
+        .char_indices()
+        .rev()
+        .find(|&(_, c)| is_word_char(c, extra))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    word_start(line, x, extra)
+}
+
+pub fn word_right(line: &str, x: usize, extra: &str) -> usize {
+    let x = line[x..]
+        .find(|c: char| is_word_char(c, extra))
+        .map_or(line.len(), |i| x + i);
+    word_end(line, x, extra)
+}
+
+pub fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+pub fn str_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+pub fn column_to_byte(text: &str, column: usize) -> usize {
+    let mut col = 0;
+    for (i, c) in text.char_indices() {
+        col += char_width(c);
+        if col > column {
+            return i;
+        }
+    }
+    text.len()
+}
+
+pub fn display_column(line: &str, byte_idx: usize, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    let mut col = 0;
+    for c in line[..byte_idx.min(line.len())].chars() {
+        match c {
+            '\t' => col += tab_width - col % tab_width,
+            _ => col += char_width(c),
+        }
+    }
+    col
+}
+
+pub fn expand_tabs(line: &str, tab_width: usize) -> String {
+    expand_tabs_until(line, tab_width, usize::MAX)
+}
+
+pub fn expand_tabs_until(line: &str, tab_width: usize, max_col: usize) -> String {
+    let tab_width = tab_width.max(1);
+    let mut out = String::with_capacity(line.len().min(max_col));
+    let mut col = 0;
+    for c in line.chars() {
+        if col >= max_col {
+            break;
+        }
+        match c {
+            '\t' => {
+                let pad = tab_width - col % tab_width;
+                out.push_str(&" ".repeat(pad));
+                col += pad;
+            }
+            _ => {
+                out.push(c);
+                col += char_width(c);
+            }
+        }
+    }
+    out
+}
+
+pub fn byte_at_display_column(line: &str, column: usize, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    let mut col = 0;
+    for (i, c) in line.char_indices() {
+        if col >= column {
+            return i;
+        }
+        match c {
+            '\t' => col += tab_width - col % tab_width,
+            _ => col += char_width(c),
+        }
+    }
+    line.len()
+}
+
+pub fn smart_home(line: &str, x: usize) -> usize {
+    let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+    if x == indent {
+        0
+    } else {
+        indent
+    }
+}
+
+pub fn clamp_to_boundary(line: &str, x: usize) -> usize {
+    let mut x = x.min(line.len());
+    while !line.is_char_boundary(x) {
+        x -= 1;
+    }
+    x
+}
+
+pub fn vertical_move_x(from: &str, x: usize, to: &str, tab_width: usize) -> usize {
+    let column = display_column(from, x, tab_width);
+    let target = byte_at_display_column(to, column, tab_width);
+    if display_column(to, target, tab_width) > column {
+        to[..target]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    } else {
+        target
+    }
+}
+
+pub fn display_width_capped(line: &str, tab_width: usize, cap: usize) -> usize {
+    let end = byte_at_display_column(line, cap, tab_width);
+    display_column(line, end, tab_width).min(cap)
+}
+
+pub fn tabs_to_spaces(lines: &mut [String], tab_size: usize) -> usize {
+    let tab_size = tab_size.max(1);
+    let mut changed = 0;
+    for line in lines.iter_mut() {
+        let (width, bytes) = leading_width(line, tab_size);
+        if line[..bytes].contains('\t') {
+            line.replace_range(..bytes, &" ".repeat(width));
+            changed += 1;
+        }
+    }
+    changed
+}
+
+pub fn spaces_to_tabs(lines: &mut [String], tab_size: usize) -> usize {
+    let tab_size = tab_size.max(1);
+    let mut changed = 0;
+    for line in lines.iter_mut() {
+        let (width, bytes) = leading_width(line, tab_size);
+        let indent = format!(
+            "{}{}",
+            "\t".repeat(width / tab_size),
+            " ".repeat(width % tab_size)
+        );
+        if line[..bytes] != indent {
+            line.replace_range(..bytes, &indent);
+            changed += 1;
+        }
+    }
+    changed
+}
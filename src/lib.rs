@@ -0,0 +1,5 @@
+pub mod config;
+pub mod controller;
+pub mod editor;
+pub mod state;
+pub mod ui;
@@ -1,12 +1,22 @@
+use crate::editor;
+use chrono::{DateTime, Local};
 use crossterm::event::{KeyCode, KeyModifiers};
 use crossterm::style::Color;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt;
+use std::fs::ReadDir;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 pub const APP_NAME: &str = "FERO";
+pub const CHORD_SEPARATOR: &str = " + ";
+pub const MIN_UNDO_LIMIT: usize = 10;
+pub const MAX_TAB_SIZE: usize = 16;
+pub const WIPE_CONFIRM_LINES: usize = 100;
+pub const WIPE_CONFIRM_WORD: &str = "WIPE";
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Mode {
@@ -18,6 +28,8 @@ pub enum Mode {
     ColorEditor,
     ConfirmWipe,
     KeyRebind,
+    Outline,
+    BufferInfo,
     Confirm(ConfirmType),
 }
 
@@ -41,20 +53,41 @@ pub enum PromptType {
     Find,
     Replace,
     GoToLine,
+    Rename,
+    ExportTheme,
+    ImportTheme,
+    AlignIndent,
+    ReplaceAll,
+    ReplaceWith,
+    FileType,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ConfirmType {
     CloseTab,
+    Overwrite,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WipeConfirm {
+    Quick,
+    Typed,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum ConfirmChoice {
     No,
     Yes,
     Cancel,
 }
 
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum CursorShape {
+    Block,
+    Bar,
+    Underline,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct KeyCombo {
     pub code: KeyCode,
@@ -78,10 +111,24 @@ pub enum KeybindAction {
     Find,
     GoToLine,
     WipeBuffer,
+    RevealInExplorer,
+    DuplicateSelection,
+    ToggleBufferSyntax,
+    BufferInfo,
+    FindNext,
+    FindPrevious,
+    DuplicateLine,
     ResetToDefault,
 }
 
 impl KeybindAction {
+    pub fn default_for(combo: KeyCombo) -> Option<Self> {
+        DEFAULT_KEYBINDS
+            .iter()
+            .find(|(bound, _)| *bound == combo)
+            .map(|&(_, action)| action)
+    }
+
     pub fn from_index(index: usize) -> Option<Self> {
         match index {
             0 => Some(KeybindAction::Menu),
@@ -99,33 +146,183 @@ impl KeybindAction {
             12 => Some(KeybindAction::Find),
             13 => Some(KeybindAction::GoToLine),
             14 => Some(KeybindAction::WipeBuffer),
-            15 => Some(KeybindAction::ResetToDefault),
+            15 => Some(KeybindAction::RevealInExplorer),
+            16 => Some(KeybindAction::DuplicateSelection),
+            17 => Some(KeybindAction::ToggleBufferSyntax),
+            18 => Some(KeybindAction::BufferInfo),
+            19 => Some(KeybindAction::FindNext),
+            20 => Some(KeybindAction::FindPrevious),
+            21 => Some(KeybindAction::DuplicateLine),
+            22 => Some(KeybindAction::ResetToDefault),
             _ => None,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+const fn bind(
+    code: KeyCode,
+    modifiers: KeyModifiers,
+    action: KeybindAction,
+) -> (KeyCombo, KeybindAction) {
+    (KeyCombo { code, modifiers }, action)
+}
+
+pub const DEFAULT_KEYBINDS: &[(KeyCombo, KeybindAction)] = &[
+    bind(KeyCode::Esc, KeyModifiers::NONE, KeybindAction::Menu),
+    bind(
+        KeyCode::Char('s'),
+        KeyModifiers::CONTROL,
+        KeybindAction::Save,
+    ),
+    bind(
+        KeyCode::Char('z'),
+        KeyModifiers::CONTROL,
+        KeybindAction::Undo,
+    ),
+    bind(
+        KeyCode::Char('y'),
+        KeyModifiers::CONTROL,
+        KeybindAction::Redo,
+    ),
+    bind(
+        KeyCode::Char('n'),
+        KeyModifiers::CONTROL,
+        KeybindAction::NewTab,
+    ),
+    bind(
+        KeyCode::Char('w'),
+        KeyModifiers::CONTROL,
+        KeybindAction::CloseTab,
+    ),
+    bind(
+        KeyCode::PageDown,
+        KeyModifiers::CONTROL,
+        KeybindAction::NextTab,
+    ),
+    bind(
+        KeyCode::PageUp,
+        KeyModifiers::CONTROL,
+        KeybindAction::PrevTab,
+    ),
+    bind(
+        KeyCode::Char('a'),
+        KeyModifiers::CONTROL,
+        KeybindAction::SelectAll,
+    ),
+    bind(
+        KeyCode::Char('c'),
+        KeyModifiers::CONTROL,
+        KeybindAction::Copy,
+    ),
+    bind(
+        KeyCode::Char('x'),
+        KeyModifiers::CONTROL,
+        KeybindAction::Cut,
+    ),
+    bind(
+        KeyCode::Char('v'),
+        KeyModifiers::CONTROL,
+        KeybindAction::Paste,
+    ),
+    bind(
+        KeyCode::Char('f'),
+        KeyModifiers::CONTROL,
+        KeybindAction::Find,
+    ),
+    bind(
+        KeyCode::Char('g'),
+        KeyModifiers::CONTROL,
+        KeybindAction::GoToLine,
+    ),
+    bind(
+        KeyCode::Char('w'),
+        KeyModifiers::ALT,
+        KeybindAction::WipeBuffer,
+    ),
+    bind(
+        KeyCode::Char('e'),
+        KeyModifiers::CONTROL,
+        KeybindAction::RevealInExplorer,
+    ),
+    bind(
+        KeyCode::Char('d'),
+        KeyModifiers::ALT,
+        KeybindAction::DuplicateSelection,
+    ),
+    bind(
+        KeyCode::Char('h'),
+        KeyModifiers::ALT,
+        KeybindAction::ToggleBufferSyntax,
+    ),
+    bind(
+        KeyCode::Char('i'),
+        KeyModifiers::ALT,
+        KeybindAction::BufferInfo,
+    ),
+    bind(KeyCode::F(3), KeyModifiers::NONE, KeybindAction::FindNext),
+    bind(
+        KeyCode::F(3),
+        KeyModifiers::SHIFT,
+        KeybindAction::FindPrevious,
+    ),
+    bind(
+        KeyCode::Char('d'),
+        KeyModifiers::CONTROL,
+        KeybindAction::DuplicateLine,
+    ),
+];
+
+#[derive(Debug, Clone, Default)]
 pub struct KeybindState {
     pub in_rebind_mode: bool,
     pub selected_action: usize,
     pub waiting_for_key: bool,
     pub pending_action: Option<usize>,
-    pub custom_binds: HashMap<KeyCombo, KeybindAction>,
+    pub custom_binds: HashMap<Vec<KeyCombo>, KeybindAction>,
     pub scroll_offset: usize,
     pub confirming_reset: bool,
+    pub pending_chord: Vec<KeyCombo>,
+    pub chord_started: Option<Instant>,
 }
 
-impl Default for KeybindState {
-    fn default() -> Self {
-        Self {
-            in_rebind_mode: false,
-            selected_action: 0,
-            waiting_for_key: false,
-            pending_action: None,
-            custom_binds: HashMap::new(),
-            scroll_offset: 0,
-            confirming_reset: false,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChordOutcome {
+    Matched(KeybindAction),
+    Pending,
+    Mismatch,
+    Unbound,
+}
+
+impl KeybindState {
+    pub fn resolve(&mut self, combo: KeyCombo, now: Instant, timeout: Duration) -> ChordOutcome {
+        if self
+            .chord_started
+            .is_some_and(|started| now.duration_since(started) > timeout)
+        {
+            self.pending_chord.clear();
+        }
+        self.chord_started = None;
+
+        let had_prefix = !self.pending_chord.is_empty();
+        let mut sequence = std::mem::take(&mut self.pending_chord);
+        sequence.push(combo);
+
+        if let Some(&action) = self.custom_binds.get(&sequence) {
+            return ChordOutcome::Matched(action);
+        }
+
+        let is_prefix = self
+            .custom_binds
+            .keys()
+            .any(|bind| bind.len() > sequence.len() && bind.starts_with(&sequence));
+        if is_prefix {
+            self.pending_chord = sequence;
+            self.chord_started = Some(now);
+            ChordOutcome::Pending
+        } else if had_prefix {
+            ChordOutcome::Mismatch
+        } else {
+            ChordOutcome::Unbound
         }
     }
 }
@@ -137,6 +334,8 @@ pub struct Config {
     pub show_header: bool,
     pub auto_save: bool,
     pub tab_size: usize,
+    #[serde(default = "default_tab_display_width")]
+    pub tab_display_width: usize,
     #[serde(default)]
     pub palette: PaletteConfig,
     #[serde(default = "default_true")]
@@ -145,12 +344,184 @@ pub struct Config {
     pub show_tab_bar: bool,
     #[serde(default)]
     pub custom_keybinds: Vec<(String, String)>,
+    #[serde(default = "default_language_indents")]
+    pub language_indents: HashMap<String, IndentConfig>,
+    #[serde(default = "default_confirm_close")]
+    pub confirm_close_default: ConfirmChoice,
+    #[serde(default = "default_undo_limit")]
+    pub undo_limit: usize,
+    #[serde(default = "default_new_tab_name")]
+    pub new_tab_name: String,
+    #[serde(default)]
+    pub new_tab_template: String,
+    #[serde(default = "default_insert_cursor")]
+    pub insert_cursor: CursorShape,
+    #[serde(default = "default_overlay_cursor")]
+    pub overlay_cursor: CursorShape,
+    #[serde(default)]
+    pub ruler_column: Option<usize>,
+    #[serde(default)]
+    pub highlight_overflow: bool,
+    #[serde(default)]
+    pub ensure_final_newline: bool,
+    #[serde(default)]
+    pub reindent_paste: bool,
+    #[serde(default = "default_true")]
+    pub explorer_preview: bool,
+    #[serde(default = "default_explorer_width")]
+    pub explorer_width: u16,
+    #[serde(default)]
+    pub always_show_tab_bar: bool,
+    #[serde(default = "default_extension_aliases")]
+    pub extension_aliases: HashMap<String, String>,
+    #[serde(default = "default_modified_glyph")]
+    pub modified_glyph: String,
+    #[serde(default = "default_unmodified_glyph")]
+    pub unmodified_glyph: String,
+    #[serde(default)]
+    pub ascii_mode: bool,
+    #[serde(default)]
+    pub save_on_focus_lost: bool,
+    #[serde(default)]
+    pub trim_edited_lines: bool,
+    #[serde(default)]
+    pub word_chars: String,
+    #[serde(default = "default_menu_key")]
+    pub menu_key: String,
+    #[serde(default)]
+    pub language_syntax: HashMap<String, bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IndentConfig {
+    pub tab_size: usize,
+    #[serde(default)]
+    pub use_hard_tabs: bool,
+}
+
+impl IndentConfig {
+    pub fn unit(&self) -> String {
+        if self.use_hard_tabs {
+            "\t".to_string()
+        } else {
+            " ".repeat(self.tab_size)
+        }
+    }
+
+    pub fn label(&self) -> String {
+        if self.use_hard_tabs {
+            format!("Tabs:{}", self.tab_size)
+        } else {
+            format!("Spaces:{}", self.tab_size)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndentStyle {
+    Tabs,
+    Spaces(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreviewSkip {
+    Binary,
+    Large,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScratchBuffer {
+    pub name: String,
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub line: usize,
+    pub kind: &'static str,
+    pub name: String,
 }
 
 fn default_true() -> bool {
     true
 }
 
+fn default_tab_display_width() -> usize {
+    4
+}
+
+fn default_explorer_width() -> u16 {
+    40
+}
+
+fn default_undo_limit() -> usize {
+    100
+}
+
+fn default_new_tab_name() -> String {
+    "untitled-{n}.txt".to_string()
+}
+
+fn default_insert_cursor() -> CursorShape {
+    CursorShape::Bar
+}
+
+fn default_overlay_cursor() -> CursorShape {
+    CursorShape::Block
+}
+
+fn default_confirm_close() -> ConfirmChoice {
+    ConfirmChoice::Yes
+}
+
+fn default_language_indents() -> HashMap<String, IndentConfig> {
+    let mut map = HashMap::new();
+    for ext in ["js", "ts", "json", "toml", "yaml", "yml"] {
+        map.insert(
+            ext.to_string(),
+            IndentConfig {
+                tab_size: 2,
+                use_hard_tabs: false,
+            },
+        );
+    }
+    map.insert(
+        "rs".to_string(),
+        IndentConfig {
+            tab_size: 4,
+            use_hard_tabs: false,
+        },
+    );
+    map.insert(
+        "go".to_string(),
+        IndentConfig {
+            tab_size: 4,
+            use_hard_tabs: true,
+        },
+    );
+    map
+}
+
+fn default_modified_glyph() -> String {
+    "●".to_string()
+}
+
+fn default_menu_key() -> String {
+    "NONE|F(10)".to_string()
+}
+
+fn default_unmodified_glyph() -> String {
+    " ".to_string()
+}
+
+fn default_extension_aliases() -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    for (ext, lang) in [("pyi", "py"), ("pyw", "py"), ("zsh", "sh"), ("rsx", "rs")] {
+        map.insert(ext.to_string(), lang.to_string());
+    }
+    map
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -159,10 +530,153 @@ impl Default for Config {
             show_header: true,
             auto_save: false,
             tab_size: 4,
+            tab_display_width: default_tab_display_width(),
             palette: PaletteConfig::default(),
             syntax_highlight: true,
             show_tab_bar: true,
             custom_keybinds: Vec::new(),
+            language_indents: default_language_indents(),
+            confirm_close_default: default_confirm_close(),
+            undo_limit: default_undo_limit(),
+            new_tab_name: default_new_tab_name(),
+            new_tab_template: String::new(),
+            insert_cursor: default_insert_cursor(),
+            overlay_cursor: default_overlay_cursor(),
+            ruler_column: None,
+            highlight_overflow: false,
+            ensure_final_newline: false,
+            reindent_paste: false,
+            explorer_preview: true,
+            explorer_width: default_explorer_width(),
+            always_show_tab_bar: false,
+            extension_aliases: default_extension_aliases(),
+            modified_glyph: default_modified_glyph(),
+            unmodified_glyph: default_unmodified_glyph(),
+            ascii_mode: false,
+            save_on_focus_lost: false,
+            trim_edited_lines: false,
+            word_chars: String::new(),
+            menu_key: default_menu_key(),
+            language_syntax: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn sanitize(&mut self) {
+        self.tab_size = self.tab_size.clamp(1, MAX_TAB_SIZE);
+        self.tab_display_width = self.tab_display_width.clamp(1, MAX_TAB_SIZE);
+        for indent in self.language_indents.values_mut() {
+            indent.tab_size = indent.tab_size.clamp(1, MAX_TAB_SIZE);
+        }
+        self.undo_limit = self.undo_limit.max(MIN_UNDO_LIMIT);
+        self.explorer_width = self.explorer_width.max(MIN_EXPLORER_WIDTH);
+        self.ruler_column = self.ruler_column.filter(|&column| column > 0);
+        if self.new_tab_name.trim().is_empty() {
+            self.new_tab_name = default_new_tab_name();
+        }
+    }
+
+    pub fn modified_marker(&self, modified: bool) -> Cow<'_, str> {
+        if modified {
+            self.glyphs(&self.modified_glyph)
+        } else {
+            self.glyphs(&self.unmodified_glyph)
+        }
+    }
+
+    pub fn glyphs<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if !self.ascii_mode || text.is_ascii() {
+            return Cow::Borrowed(text);
+        }
+        let mut out = text.to_string();
+        for (glyph, ascii) in ASCII_GLYPHS {
+            out = out.replace(glyph, ascii);
+        }
+        Cow::Owned(out)
+    }
+
+    pub fn tab_bar_visible(&self, buffer_count: usize) -> bool {
+        self.show_tab_bar && (self.always_show_tab_bar || buffer_count > 1)
+    }
+
+    pub fn cursor_shape_for(&self, mode: Mode, input_mode: bool) -> CursorShape {
+        if mode == Mode::Editing || input_mode {
+            self.insert_cursor
+        } else {
+            self.overlay_cursor
+        }
+    }
+
+    pub fn language_for<'a>(&'a self, filename: &'a str) -> &'a str {
+        let file_ext = filename.rsplit('.').next().unwrap_or("");
+        self.extension_aliases
+            .get(file_ext)
+            .map(String::as_str)
+            .unwrap_or(file_ext)
+    }
+
+    pub fn editor_height(&self, term_h: u16, tab_bar_visible: bool) -> usize {
+        term_h.saturating_sub(
+            (if self.show_header { 1 } else { 0 })
+                + (if tab_bar_visible { 1 } else { 0 })
+                + (if self.show_status_bar { 1 } else { 0 }),
+        ) as usize
+    }
+
+    pub fn filetype_for<'a>(&'a self, buf: &'a Buffer) -> &'a str {
+        match &buf.filetype_override {
+            Some(filetype) => self
+                .extension_aliases
+                .get(filetype)
+                .map(String::as_str)
+                .unwrap_or(filetype),
+            None if buf.filename.contains('.') => self.language_for(&buf.filename),
+            None => "plain",
+        }
+    }
+
+    pub fn syntax_for_language(&self, filename: &str) -> bool {
+        self.syntax_for_filetype(self.language_for(filename))
+    }
+
+    pub fn syntax_for_buffer(&self, buf: &Buffer) -> bool {
+        self.syntax_for_filetype(self.filetype_for(buf))
+    }
+
+    fn syntax_for_filetype(&self, filetype: &str) -> bool {
+        self.syntax_highlight && self.language_syntax.get(filetype).copied().unwrap_or(true)
+    }
+
+    pub fn indent_for(&self, filename: &str) -> IndentConfig {
+        self.indent_for_extension(filename.rsplit('.').next().unwrap_or(""))
+    }
+
+    fn indent_for_extension(&self, file_ext: &str) -> IndentConfig {
+        self.language_indents
+            .get(file_ext)
+            .copied()
+            .unwrap_or(IndentConfig {
+                tab_size: self.tab_size,
+                use_hard_tabs: false,
+            })
+    }
+
+    pub fn indent_for_buffer(&self, buf: &Buffer) -> IndentConfig {
+        let base = match &buf.filetype_override {
+            Some(_) => self.indent_for_extension(self.filetype_for(buf)),
+            None => self.indent_for(&buf.filename),
+        };
+        match buf.indent_style {
+            Some(IndentStyle::Tabs) => IndentConfig {
+                tab_size: base.tab_size,
+                use_hard_tabs: true,
+            },
+            Some(IndentStyle::Spaces(width)) => IndentConfig {
+                tab_size: width,
+                use_hard_tabs: false,
+            },
+            None => base,
         }
     }
 }
@@ -197,6 +711,11 @@ impl KeyCombo {
             code_str.strip_prefix("Char('").and_then(|s| s.strip_suffix("')"))
         {
             char_part.chars().next().map(KeyCode::Char)
+        } else if let Some(num) = code_str
+            .strip_prefix("F(")
+            .and_then(|s| s.strip_suffix(')'))
+        {
+            num.parse().ok().map(KeyCode::F)
         } else {
             match code_str {
                 "Enter" => Some(KeyCode::Enter),
@@ -208,6 +727,10 @@ impl KeyCombo {
                 "Down" => Some(KeyCode::Down),
                 "Delete" => Some(KeyCode::Delete),
                 "Esc" => Some(KeyCode::Esc),
+                "Home" => Some(KeyCode::Home),
+                "End" => Some(KeyCode::End),
+                "PageUp" => Some(KeyCode::PageUp),
+                "PageDown" => Some(KeyCode::PageDown),
                 _ => None,
             }
         };
@@ -217,10 +740,41 @@ impl KeyCombo {
             modifiers,
         })
     }
+
+    pub fn parse_sequence(s: &str) -> Option<Vec<Self>> {
+        s.split(CHORD_SEPARATOR).map(Self::from_string).collect()
+    }
+
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push_str("Ctrl+");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            label.push_str("Alt+");
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            label.push_str("Shift+");
+        }
+        match self.code {
+            KeyCode::Char(c) => label.extend(c.to_uppercase()),
+            KeyCode::F(n) => label.push_str(&format!("F{}", n)),
+            code => label.push_str(&format!("{:?}", code)),
+        }
+        label
+    }
+
+    pub fn format_sequence(sequence: &[Self]) -> String {
+        sequence
+            .iter()
+            .map(|combo| combo.to_string())
+            .collect::<Vec<_>>()
+            .join(CHORD_SEPARATOR)
+    }
 }
 
 impl KeybindAction {
-    pub fn to_str(&self) -> &'static str {
+    pub fn to_str(self) -> &'static str {
         match self {
             KeybindAction::Menu => "Menu",
             KeybindAction::Save => "Save",
@@ -237,6 +791,13 @@ impl KeybindAction {
             KeybindAction::Find => "Find",
             KeybindAction::GoToLine => "GoToLine",
             KeybindAction::WipeBuffer => "WipeBuffer",
+            KeybindAction::RevealInExplorer => "RevealInExplorer",
+            KeybindAction::DuplicateSelection => "DuplicateSelection",
+            KeybindAction::ToggleBufferSyntax => "ToggleBufferSyntax",
+            KeybindAction::BufferInfo => "BufferInfo",
+            KeybindAction::FindNext => "FindNext",
+            KeybindAction::FindPrevious => "FindPrevious",
+            KeybindAction::DuplicateLine => "DuplicateLine",
             KeybindAction::ResetToDefault => "ResetToDefault",
         }
     }
@@ -261,6 +822,13 @@ impl FromStr for KeybindAction {
             "Find" => Ok(KeybindAction::Find),
             "GoToLine" => Ok(KeybindAction::GoToLine),
             "WipeBuffer" => Ok(KeybindAction::WipeBuffer),
+            "RevealInExplorer" => Ok(KeybindAction::RevealInExplorer),
+            "DuplicateSelection" => Ok(KeybindAction::DuplicateSelection),
+            "ToggleBufferSyntax" => Ok(KeybindAction::ToggleBufferSyntax),
+            "BufferInfo" => Ok(KeybindAction::BufferInfo),
+            "FindNext" => Ok(KeybindAction::FindNext),
+            "FindPrevious" => Ok(KeybindAction::FindPrevious),
+            "DuplicateLine" => Ok(KeybindAction::DuplicateLine),
             "ResetToDefault" => Ok(KeybindAction::ResetToDefault),
             _ => Err(()),
         }
@@ -328,6 +896,192 @@ impl Default for PaletteConfig {
     }
 }
 
+pub const COLOR_EDITOR_ROWS: usize = 10;
+pub const HELP_CHROME_ROWS: usize = 10;
+pub const OUTLINE_ROWS: usize = 12;
+pub const MIN_EXPLORER_WIDTH: u16 = 16;
+pub const EXPLORER_BATCH: usize = 500;
+pub const TAB_NAME_MAX: usize = 24;
+pub const SCRATCH_MAX_BUFFERS: usize = 8;
+pub const SCRATCH_MAX_BYTES: usize = 256 * 1024;
+
+pub const HELP_BINDINGS: &[(&str, &str)] = &[
+    ("Arrows", "Move cursor"),
+    ("Shift+Arrows", "Select text"),
+    ("Home/End", "Indent, line start / end (Shift selects)"),
+    ("Ctrl+Left/Right", "Move by word"),
+    ("Ctrl+A", "Select all"),
+    ("Ctrl+C/X/V", "Copy / Cut / Paste"),
+    ("Enter", "New line"),
+    ("Ctrl+Tab", "Last used tab"),
+    ("Ctrl+N/W", "New / Close tab"),
+    ("Ctrl+PgUp/PgDn", "Previous / Next tab"),
+    ("Ctrl+F/G", "Find / Go to line"),
+    ("Ctrl+D", "Duplicate line"),
+    ("Alt+D", "Duplicate selection"),
+    ("Alt+W", "Wipe buffer"),
+    ("Ctrl+S", "Save"),
+    ("Ctrl+E", "Explore file's folder"),
+    ("Alt+H", "Syntax for this tab"),
+    ("Alt+I", "Buffer info"),
+    ("Ctrl+Z/Y", "Undo / Redo"),
+    ("F3/Shift+F3", "Find next / previous"),
+    ("Ctrl+Backspace", "Delete word"),
+    ("Ctrl+Up/Down", "Jump top/bottom"),
+    ("PgUp/PgDn", "Page up / down"),
+    ("Tab/Shift+Tab", "Indent / dedent lines"),
+    ("Alt+Up/Down", "Move line"),
+    ("Alt+Left/Right", "Scroll sideways"),
+];
+
+// The Esc and menu key rows are added in front of HELP_BINDINGS when drawing.
+pub const HELP_ROWS: usize = HELP_BINDINGS.len() + 2;
+
+pub fn help_visible_rows(term_h: u16) -> usize {
+    (term_h as usize)
+        .saturating_sub(HELP_CHROME_ROWS)
+        .min(HELP_ROWS)
+}
+
+pub const DIR_MARKER: &str = "📁 ";
+pub const FILE_MARKER: &str = "📄 ";
+
+pub const ASCII_GLYPHS: &[(&str, &str)] = &[
+    ("📁", "+"),
+    ("📄", "-"),
+    ("▶", ">"),
+    ("↑↓", "Up/Down"),
+    ("•", "|"),
+    ("…", "..."),
+    ("—", "-"),
+    ("│", "|"),
+    ("»", ">"),
+    ("‹", "<"),
+    ("●", "*"),
+];
+
+pub const PALETTE_FIELDS: [&str; 25] = [
+    "ui_background",
+    "ui_foreground",
+    "ui_border",
+    "status_bar_bg",
+    "status_bar_fg",
+    "header_bg",
+    "header_fg",
+    "editor_background",
+    "editor_foreground",
+    "line_number_bg",
+    "line_number_fg",
+    "cursor",
+    "selection_bg",
+    "selection_fg",
+    "syntax_keyword",
+    "syntax_string",
+    "syntax_comment",
+    "syntax_function",
+    "syntax_type",
+    "syntax_constant",
+    "accent_primary",
+    "accent_secondary",
+    "match_highlight",
+    "error",
+    "warning",
+];
+
+impl PaletteConfig {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        let value = match name {
+            "ui_background" => Some(&self.ui_background),
+            "ui_foreground" => Some(&self.ui_foreground),
+            "ui_border" => Some(&self.ui_border),
+            "status_bar_bg" => Some(&self.status_bar_bg),
+            "status_bar_fg" => Some(&self.status_bar_fg),
+            "header_bg" => Some(&self.header_bg),
+            "header_fg" => Some(&self.header_fg),
+            "editor_background" => Some(&self.editor_background),
+            "editor_foreground" => Some(&self.editor_foreground),
+            "line_number_bg" => Some(&self.line_number_bg),
+            "line_number_fg" => Some(&self.line_number_fg),
+            "cursor" => Some(&self.cursor),
+            "selection_bg" => Some(&self.selection_bg),
+            "selection_fg" => Some(&self.selection_fg),
+            "syntax_keyword" => Some(&self.syntax_keyword),
+            "syntax_string" => Some(&self.syntax_string),
+            "syntax_comment" => Some(&self.syntax_comment),
+            "syntax_function" => Some(&self.syntax_function),
+            "syntax_type" => Some(&self.syntax_type),
+            "syntax_constant" => Some(&self.syntax_constant),
+            "accent_primary" => Some(&self.accent_primary),
+            "accent_secondary" => Some(&self.accent_secondary),
+            "match_highlight" => Some(&self.match_highlight),
+            "error" => Some(&self.error),
+            "warning" => Some(&self.warning),
+            _ => None,
+        };
+        value.map(|s| s.as_str())
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut String> {
+        match name {
+            "ui_background" => Some(&mut self.ui_background),
+            "ui_foreground" => Some(&mut self.ui_foreground),
+            "ui_border" => Some(&mut self.ui_border),
+            "status_bar_bg" => Some(&mut self.status_bar_bg),
+            "status_bar_fg" => Some(&mut self.status_bar_fg),
+            "header_bg" => Some(&mut self.header_bg),
+            "header_fg" => Some(&mut self.header_fg),
+            "editor_background" => Some(&mut self.editor_background),
+            "editor_foreground" => Some(&mut self.editor_foreground),
+            "line_number_bg" => Some(&mut self.line_number_bg),
+            "line_number_fg" => Some(&mut self.line_number_fg),
+            "cursor" => Some(&mut self.cursor),
+            "selection_bg" => Some(&mut self.selection_bg),
+            "selection_fg" => Some(&mut self.selection_fg),
+            "syntax_keyword" => Some(&mut self.syntax_keyword),
+            "syntax_string" => Some(&mut self.syntax_string),
+            "syntax_comment" => Some(&mut self.syntax_comment),
+            "syntax_function" => Some(&mut self.syntax_function),
+            "syntax_type" => Some(&mut self.syntax_type),
+            "syntax_constant" => Some(&mut self.syntax_constant),
+            "accent_primary" => Some(&mut self.accent_primary),
+            "accent_secondary" => Some(&mut self.accent_secondary),
+            "match_highlight" => Some(&mut self.match_highlight),
+            "error" => Some(&mut self.error),
+            "warning" => Some(&mut self.warning),
+            _ => None,
+        }
+    }
+
+    pub fn set(&mut self, name: &str, hex: &str) -> bool {
+        match self.field_mut(name) {
+            Some(field) => {
+                *field = hex.to_string();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn from_entries(entries: &[ColorEntry]) -> Self {
+        let mut config = Self::default();
+        for e in entries {
+            if e.current_hex.len() == 7 && e.current_hex.starts_with('#') {
+                config.set(&e.name, &e.current_hex);
+            }
+        }
+        config
+    }
+
+    pub fn invalid_entry(&self) -> Option<&'static str> {
+        PALETTE_FIELDS.iter().copied().find(|name| {
+            let hex = self.get(name).unwrap_or("");
+            !(hex.len() == 7
+                && hex.starts_with('#')
+                && hex[1..].chars().all(|c| c.is_ascii_hexdigit()))
+        })
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Palette {
     pub ui_background: Color,
@@ -357,11 +1111,13 @@ pub struct Palette {
     pub warning: Color,
 }
 
-impl Palette {
-    pub fn default() -> Self {
+impl Default for Palette {
+    fn default() -> Self {
         Self::from_config(&PaletteConfig::default())
     }
+}
 
+impl Palette {
     pub fn from_config(cfg: &PaletteConfig) -> Self {
         Self {
             ui_background: Self::hex_to_color(&cfg.ui_background),
@@ -392,7 +1148,7 @@ impl Palette {
         }
     }
 
-    pub fn to_config(&self) -> PaletteConfig {
+    pub fn to_config(self) -> PaletteConfig {
         PaletteConfig {
             ui_background: Self::to_hex(self.ui_background),
             ui_foreground: Self::to_hex(self.ui_foreground),
@@ -453,36 +1209,22 @@ pub struct ColorEntry {
 impl AppState {
     pub fn populate_color_entries(&mut self) {
         let config = self.current_palette.to_config();
-        self.color_entries = vec![
-            ColorEntry { name: "ui_background".to_string(), current_hex: config.ui_background },
-            ColorEntry { name: "ui_foreground".to_string(), current_hex: config.ui_foreground },
-            ColorEntry { name: "ui_border".to_string(), current_hex: config.ui_border },
-            ColorEntry { name: "status_bar_bg".to_string(), current_hex: config.status_bar_bg },
-            ColorEntry { name: "status_bar_fg".to_string(), current_hex: config.status_bar_fg },
-            ColorEntry { name: "header_bg".to_string(), current_hex: config.header_bg },
-            ColorEntry { name: "header_fg".to_string(), current_hex: config.header_fg },
-            ColorEntry { name: "editor_background".to_string(), current_hex: config.editor_background },
-            ColorEntry { name: "editor_foreground".to_string(), current_hex: config.editor_foreground },
-            ColorEntry { name: "line_number_bg".to_string(), current_hex: config.line_number_bg },
-            ColorEntry { name: "line_number_fg".to_string(), current_hex: config.line_number_fg },
-            ColorEntry { name: "cursor".to_string(), current_hex: config.cursor },
-            ColorEntry { name: "selection_bg".to_string(), current_hex: config.selection_bg },
-            ColorEntry { name: "selection_fg".to_string(), current_hex: config.selection_fg },
-            ColorEntry { name: "syntax_keyword".to_string(), current_hex: config.syntax_keyword },
-            ColorEntry { name: "syntax_string".to_string(), current_hex: config.syntax_string },
-            ColorEntry { name: "syntax_comment".to_string(), current_hex: config.syntax_comment },
-            ColorEntry { name: "syntax_function".to_string(), current_hex: config.syntax_function },
-            ColorEntry { name: "syntax_type".to_string(), current_hex: config.syntax_type },
-            ColorEntry { name: "syntax_constant".to_string(), current_hex: config.syntax_constant },
-            ColorEntry { name: "accent_primary".to_string(), current_hex: config.accent_primary },
-            ColorEntry { name: "accent_secondary".to_string(), current_hex: config.accent_secondary },
-            ColorEntry { name: "match_highlight".to_string(), current_hex: config.match_highlight },
-            ColorEntry { name: "error".to_string(), current_hex: config.error },
-            ColorEntry { name: "warning".to_string(), current_hex: config.warning },
-        ];
+        self.color_entries = PALETTE_FIELDS
+            .iter()
+            .map(|name| ColorEntry {
+                name: name.to_string(),
+                current_hex: config.get(name).unwrap_or_default().to_string(),
+            })
+            .collect();
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct FindCache {
+    pub query: String,
+    pub matches: Vec<(usize, usize)>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Selection {
     pub start_x: usize,
@@ -501,6 +1243,10 @@ impl Selection {
         }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.start_x == self.end_x && self.start_y == self.end_y
+    }
+
     pub fn normalized(&self) -> (usize, usize, usize, usize) {
         if self.start_y < self.end_y || (self.start_y == self.end_y && self.start_x <= self.end_x) {
             (self.start_x, self.start_y, self.end_x, self.end_y)
@@ -510,9 +1256,73 @@ impl Selection {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferInfo {
+    pub lines: usize,
+    pub chars: usize,
+    pub words: usize,
+    pub selected_chars: Option<usize>,
+    pub cursor_line: usize,
+    pub cursor_column: usize,
+    pub path: String,
+    pub modified: bool,
+    pub language: String,
+    pub line_ending: &'static str,
+}
+
+impl BufferInfo {
+    pub fn new(buf: &Buffer, selection: Option<&Selection>, config: &Config) -> Self {
+        let newlines = buf.lines.len().saturating_sub(1);
+        let selected_chars = selection.map(|sel| {
+            let (sx, sy, ex, ey) = sel.normalized();
+            if sy == ey {
+                buf.lines[sy][sx..ex].chars().count()
+            } else {
+                buf.lines[sy][sx..].chars().count()
+                    + buf.lines[sy + 1..ey]
+                        .iter()
+                        .map(|line| line.chars().count())
+                        .sum::<usize>()
+                    + buf.lines[ey][..ex].chars().count()
+                    + (ey - sy)
+            }
+        });
+        let language = config.filetype_for(buf).to_string();
+        Self {
+            lines: buf.lines.len(),
+            chars: buf
+                .lines
+                .iter()
+                .map(|line| line.chars().count())
+                .sum::<usize>()
+                + newlines,
+            words: buf
+                .lines
+                .iter()
+                .map(|line| line.split_whitespace().count())
+                .sum(),
+            selected_chars,
+            cursor_line: buf.cursor_y + 1,
+            cursor_column: editor::display_column(
+                &buf.lines[buf.cursor_y],
+                buf.cursor_x,
+                config.tab_display_width,
+            ) + 1,
+            path: buf.file_path.as_ref().map_or_else(
+                || "(not saved)".to_string(),
+                |path| path.display().to_string(),
+            ),
+            modified: buf.modified,
+            language,
+            line_ending: buf.line_ending,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Buffer {
     pub lines: Vec<String>,
+    // byte offset into the current line, always on a char boundary
     pub cursor_x: usize,
     pub cursor_y: usize,
 
@@ -522,6 +1332,16 @@ pub struct Buffer {
     pub filename: String,
     pub file_path: Option<PathBuf>,
     pub modified: bool,
+    pub indent_style: Option<IndentStyle>,
+    pub last_saved: Option<DateTime<Local>>,
+    pub syntax_override: Option<bool>,
+    pub filetype_override: Option<String>,
+    pub line_ending: &'static str,
+    pub edited_line: Option<usize>,
+    pub undo_stack: Vec<UndoState>,
+    pub redo_stack: Vec<UndoState>,
+    pub undo_shadow: Vec<String>,
+    pub undo_pending: Option<(usize, usize)>,
 }
 
 impl Buffer {
@@ -535,17 +1355,190 @@ impl Buffer {
             filename,
             file_path: None,
             modified: false,
+            indent_style: None,
+            last_saved: None,
+            syntax_override: None,
+            filetype_override: None,
+            line_ending: "LF",
+            edited_line: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            undo_shadow: Vec::new(),
+            undo_pending: None,
+        }
+    }
+
+    fn record_pending_undo(&mut self, limit: usize) {
+        let (start, old_lines, new_len) = sync_lines(&mut self.undo_shadow, &self.lines);
+        let changed = new_len > 0 || !old_lines.is_empty();
+        if let Some((cursor_x, cursor_y)) = self.undo_pending.take().filter(|_| changed) {
+            self.redo_stack.clear();
+            let state = UndoState {
+                start,
+                old_lines,
+                new_len,
+                cursor_x,
+                cursor_y,
+            };
+            push_capped(&mut self.undo_stack, state, limit);
+        }
+    }
+
+    pub fn push_undo(&mut self, limit: usize) {
+        self.record_pending_undo(limit);
+        self.undo_pending = Some((self.cursor_x, self.cursor_y));
+    }
+
+    pub fn undo_depth(&self) -> usize {
+        self.undo_stack.len() + usize::from(self.undo_pending.is_some())
+    }
+
+    pub fn undo(&mut self, limit: usize) -> bool {
+        self.record_pending_undo(limit);
+        match self.undo_stack.pop() {
+            Some(state) => {
+                let reverse = self.apply_undo_state(state);
+                push_capped(&mut self.redo_stack, reverse, limit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self, limit: usize) -> bool {
+        self.record_pending_undo(limit);
+        match self.redo_stack.pop() {
+            Some(state) => {
+                let reverse = self.apply_undo_state(state);
+                push_capped(&mut self.undo_stack, reverse, limit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn apply_undo_state(&mut self, state: UndoState) -> UndoState {
+        let start = state.start.min(self.lines.len());
+        let end = (start + state.new_len).min(self.lines.len());
+        let inserted = state.old_lines.len();
+        let removed = self.lines.splice(start..end, state.old_lines).collect();
+        let touched = start..start + inserted.max(end - start);
+        if inserted != end - start || self.edited_line.is_some_and(|y| touched.contains(&y)) {
+            self.edited_line = None;
+        }
+        let reverse = UndoState {
+            start,
+            old_lines: removed,
+            new_len: inserted,
+            cursor_x: self.cursor_x,
+            cursor_y: self.cursor_y,
+        };
+
+        if self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.cursor_y = state.cursor_y.min(self.lines.len() - 1);
+        self.cursor_x = editor::clamp_to_boundary(&self.lines[self.cursor_y], state.cursor_x);
+        self.modified = true;
+        sync_lines(&mut self.undo_shadow, &self.lines);
+        reverse
+    }
+
+    pub fn trim_left_line(&mut self, previous_y: usize) {
+        if self.cursor_y == previous_y || self.edited_line != Some(previous_y) {
+            return;
+        }
+        if let Some(line) = self.lines.get_mut(previous_y) {
+            let len = line.trim_end_matches([' ', '\t']).len();
+            line.truncate(len);
+        }
+        self.edited_line = None;
+    }
+
+    pub fn scroll_horizontal(
+        &mut self,
+        delta: isize,
+        view_width: usize,
+        view_height: usize,
+        tab_width: usize,
+    ) {
+        let longest = self
+            .lines
+            .iter()
+            .skip(self.viewport_offset_y)
+            .take(view_height)
+            .map(|line| editor::display_column(line, line.len(), tab_width))
+            .max()
+            .unwrap_or(0);
+        let max_offset = longest.saturating_sub(view_width);
+        let offset = self.viewport_offset_x.saturating_add_signed(delta);
+        self.viewport_offset_x = offset.min(max_offset);
+    }
+
+    pub fn syntax_enabled(&self, global: bool) -> bool {
+        self.syntax_override.unwrap_or(global)
+    }
+
+    pub fn wipe_confirm(&self, line_threshold: usize) -> WipeConfirm {
+        let empty = self.lines.len() == 1 && self.lines[0].is_empty();
+        if !empty && (self.modified || self.lines.len() > line_threshold) {
+            WipeConfirm::Typed
+        } else {
+            WipeConfirm::Quick
+        }
+    }
+
+    pub fn gutter_width(&self, show_line_numbers: bool) -> u16 {
+        if !show_line_numbers {
+            return 0;
+        }
+        let digits = self.lines.len().max(1).to_string().len();
+        (digits + 2).min(u16::MAX as usize) as u16
+    }
+
+    pub fn save_path(&self) -> String {
+        match &self.file_path {
+            Some(path) => path.to_string_lossy().to_string(),
+            None => self.filename.clone(),
         }
     }
 }
 
 #[derive(Clone)]
 pub struct UndoState {
-    pub lines: Vec<String>,
+    pub start: usize,
+    pub old_lines: Vec<String>,
+    pub new_len: usize,
     pub cursor_x: usize,
     pub cursor_y: usize,
 }
 
+fn push_capped(stack: &mut Vec<UndoState>, state: UndoState, limit: usize) {
+    let limit = limit.max(MIN_UNDO_LIMIT);
+    if stack.len() >= limit {
+        stack.drain(..=stack.len() - limit);
+    }
+    stack.push(state);
+}
+
+fn sync_lines(shadow: &mut Vec<String>, lines: &[String]) -> (usize, Vec<String>, usize) {
+    let prefix = shadow.iter().zip(lines).take_while(|(a, b)| a == b).count();
+    let max_suffix = shadow.len().min(lines.len()) - prefix;
+    let suffix = shadow
+        .iter()
+        .rev()
+        .zip(lines.iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_end = shadow.len() - suffix;
+    let new_end = lines.len() - suffix;
+    let old_lines = shadow
+        .splice(prefix..old_end, lines[prefix..new_end].iter().cloned())
+        .collect();
+    (prefix, old_lines, new_end - prefix)
+}
+
 pub struct AppState {
     pub buffers: Vec<Buffer>,
     pub active_buffer: usize,
@@ -553,6 +1546,9 @@ pub struct AppState {
     pub explorer_files: Vec<String>,
     pub explorer_idx: usize,
     pub explorer_offset: usize,
+    pub explorer_preview: Vec<String>,
+    pub explorer_pending: Option<ReadDir>,
+    pub hint_dismissed: bool,
     pub input_mode: bool,
     pub input_buffer: String,
     pub prompt_type: PromptType,
@@ -560,16 +1556,34 @@ pub struct AppState {
     pub current_palette: Palette,
     pub color_entries: Vec<ColorEntry>,
     pub color_editor_idx: usize,
+    pub color_editor_offset: usize,
     pub editing_hex: bool,
     pub selection: Option<Selection>,
-    pub undo_stack: Vec<UndoState>,
-    pub redo_stack: Vec<UndoState>,
+    pub find_cache: Option<FindCache>,
+    pub last_search: Option<String>,
+    pub undo_limit: usize,
     pub status_flash: Option<String>,
     pub status_flash_timer: u8,
     pub confirm_mode: Option<ConfirmType>,
     pub confirm_choice: ConfirmChoice,
     pub clipboard: String,
     pub keybind_state: KeybindState,
+    pub untitled_count: usize,
+    pub buffer_mru: Vec<usize>,
+    pub outline: Vec<Symbol>,
+    pub outline_idx: usize,
+    pub outline_offset: usize,
+    pub help_offset: usize,
+    pub shown_save_age: String,
+    pub pending_rename: Option<PathBuf>,
+    pub pending_replace: Option<(String, bool)>,
+    pub wipe_input: String,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AppState {
@@ -581,6 +1595,9 @@ impl AppState {
             explorer_files: Vec::new(),
             explorer_idx: 0,
             explorer_offset: 0,
+            explorer_preview: Vec::new(),
+            explorer_pending: None,
+            hint_dismissed: false,
             input_mode: false,
             input_buffer: String::new(),
             prompt_type: PromptType::Find,
@@ -588,19 +1605,52 @@ impl AppState {
             current_palette: Palette::default(),
             color_entries: Vec::new(),
             color_editor_idx: 0,
+            color_editor_offset: 0,
             editing_hex: false,
             selection: None,
-            undo_stack: Vec::new(),
-            redo_stack: Vec::new(),
+            find_cache: None,
+            last_search: None,
+            undo_limit: default_undo_limit(),
             status_flash: None,
             status_flash_timer: 0,
             confirm_mode: None,
-            confirm_choice: ConfirmChoice::No,
+            confirm_choice: ConfirmChoice::Yes,
             clipboard: String::new(),
             keybind_state: KeybindState::default(),
+            untitled_count: 0,
+            buffer_mru: vec![0],
+            outline: Vec::new(),
+            outline_idx: 0,
+            outline_offset: 0,
+            help_offset: 0,
+            shown_save_age: String::new(),
+            pending_rename: None,
+            pending_replace: None,
+            wipe_input: String::new(),
         }
     }
 
+    pub fn rename_current_file(&mut self, target: PathBuf) -> std::io::Result<()> {
+        let buf = self.current_buffer_mut();
+        std::fs::rename(buf.save_path(), &target)?;
+        buf.filename = target
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| target.to_string_lossy().to_string());
+        buf.file_path = Some(target);
+        Ok(())
+    }
+
+    pub fn explorer_start_dir(&self) -> PathBuf {
+        self.current_buffer()
+            .file_path
+            .as_deref()
+            .and_then(|path| path.parent())
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(|dir| dir.to_path_buf())
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
+    }
+
     pub fn current_buffer(&self) -> &Buffer {
         &self.buffers[self.active_buffer]
     }
@@ -609,47 +1659,203 @@ impl AppState {
         &mut self.buffers[self.active_buffer]
     }
 
+    pub fn next_untitled_name(&mut self, pattern: &str) -> String {
+        self.untitled_count += 1;
+        pattern.replace("{n}", &self.untitled_count.to_string())
+    }
+
+    pub fn unique_buffer_name(&self, name: &str) -> String {
+        let taken = |candidate: &str| self.buffers.iter().any(|b| b.filename == candidate);
+        if !taken(name) {
+            return name.to_string();
+        }
+        let (stem, ext) = match name.rfind('.') {
+            Some(i) if i > 0 => name.split_at(i),
+            _ => (name, ""),
+        };
+        (2..)
+            .map(|n| format!("{}-{}{}", stem, n, ext))
+            .find(|candidate| !taken(candidate))
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    pub fn new_buffer(&mut self, config: &Config) -> Buffer {
+        let name = self.next_untitled_name(&config.new_tab_name);
+        let mut buf = Buffer::new(self.unique_buffer_name(&name));
+        if !config.new_tab_template.is_empty() {
+            buf.lines = config.new_tab_template.lines().map(String::from).collect();
+        }
+        buf
+    }
+
+    pub fn open_new_buffer(&mut self, config: &Config) {
+        let buf = self.new_buffer(config);
+        self.add_buffer(buf);
+    }
+
+    pub fn show_empty_hint(&self) -> bool {
+        let buf = self.current_buffer();
+        !self.hint_dismissed
+            && self.buffers.len() == 1
+            && buf.file_path.is_none()
+            && !buf.modified
+            && buf.lines.len() == 1
+            && buf.lines[0].is_empty()
+    }
+
+    pub fn scratch_buffers(&self) -> Vec<ScratchBuffer> {
+        self.buffers
+            .iter()
+            .filter(|buf| buf.file_path.is_none())
+            .filter(|buf| buf.lines.iter().any(|line| !line.is_empty()))
+            .filter(|buf| {
+                buf.lines.iter().map(|line| line.len() + 1).sum::<usize>() <= SCRATCH_MAX_BYTES
+            })
+            .take(SCRATCH_MAX_BUFFERS)
+            .map(|buf| ScratchBuffer {
+                name: buf.filename.clone(),
+                lines: buf.lines.clone(),
+            })
+            .collect()
+    }
+
+    pub fn restore_scratch(&mut self, scratch: Vec<ScratchBuffer>) -> usize {
+        let restored = scratch.len();
+        if restored == 0 {
+            return 0;
+        }
+        let untouched = self.buffers.len() == 1
+            && self.buffers[0].file_path.is_none()
+            && !self.buffers[0].modified;
+        if untouched {
+            self.buffers.clear();
+            self.buffer_mru.clear();
+        }
+        for entry in scratch {
+            let mut buf = Buffer::new(entry.name);
+            buf.lines = if entry.lines.is_empty() {
+                vec![String::new()]
+            } else {
+                entry.lines
+            };
+            buf.modified = true;
+            self.add_buffer(buf);
+        }
+        restored
+    }
+
+    pub fn add_buffer(&mut self, buf: Buffer) {
+        self.buffers.push(buf);
+        self.switch_buffer(self.buffers.len() - 1);
+    }
+
+    pub fn switch_buffer(&mut self, idx: usize) {
+        let count = self.buffers.len();
+        if idx >= count {
+            return;
+        }
+        self.active_buffer = idx;
+        self.find_cache = None;
+        self.buffer_mru.retain(|&i| i != idx && i < count);
+        self.buffer_mru.insert(0, idx);
+    }
+
+    pub fn next_buffer(&mut self) {
+        if self.buffers.len() > 1 {
+            self.switch_buffer((self.active_buffer + 1) % self.buffers.len());
+        }
+    }
+
+    pub fn prev_buffer(&mut self) {
+        if self.buffers.len() > 1 {
+            let idx = self.active_buffer.checked_sub(1);
+            self.switch_buffer(idx.unwrap_or(self.buffers.len() - 1));
+        }
+    }
+
+    pub fn recent_buffer(&mut self) {
+        if let Some(&idx) = self.buffer_mru.get(1) {
+            self.switch_buffer(idx);
+        }
+    }
+
+    pub fn oldest_buffer(&mut self) {
+        if self.buffer_mru.len() > 1 {
+            if let Some(&idx) = self.buffer_mru.last() {
+                self.switch_buffer(idx);
+            }
+        }
+    }
+
+    pub fn close_buffer(&mut self, idx: usize) {
+        if self.buffers.len() <= 1 || idx >= self.buffers.len() {
+            return;
+        }
+        self.buffers.remove(idx);
+        self.buffer_mru.retain(|&i| i != idx);
+        for i in self.buffer_mru.iter_mut() {
+            if *i > idx {
+                *i -= 1;
+            }
+        }
+        let active = match self.active_buffer {
+            current if current == idx => self.buffer_mru.first().copied(),
+            current if current > idx => Some(current - 1),
+            current => Some(current),
+        };
+        let fallback = self.active_buffer.min(self.buffers.len() - 1);
+        self.switch_buffer(active.unwrap_or(fallback));
+    }
+
     pub fn push_undo(&mut self) {
-        let lines = self.buffers[self.active_buffer].lines.clone();
-        let cursor_x = self.buffers[self.active_buffer].cursor_x;
-        let cursor_y = self.buffers[self.active_buffer].cursor_y;
+        self.find_cache = None;
+        let limit = self.undo_limit;
+        self.current_buffer_mut().push_undo(limit);
+    }
 
-        if self.undo_stack.len() >= 100 {
-            self.undo_stack.remove(0);
+    pub fn trim_left_line(&mut self, previous_y: usize) {
+        let buf = self.current_buffer();
+        let trailing = buf.cursor_y != previous_y
+            && buf.edited_line == Some(previous_y)
+            && buf
+                .lines
+                .get(previous_y)
+                .is_some_and(|line| line.ends_with([' ', '\t']));
+        if trailing {
+            self.push_undo();
         }
-        self.undo_stack.push(UndoState {
-            lines,
-            cursor_x,
-            cursor_y,
-        });
-        self.redo_stack.clear();
+        self.current_buffer_mut().trim_left_line(previous_y);
     }
 
-    pub fn ensure_cursor_visible(
-        &mut self,
-        term_w: u16,
-        term_h: u16,
-        show_line_numbers: bool,
-        show_status_bar: bool,
-        show_header: bool,
-        show_tab_bar: bool,
-    ) {
+    pub fn undo(&mut self) -> bool {
+        self.find_cache = None;
+        let limit = self.undo_limit;
+        self.current_buffer_mut().undo(limit)
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.find_cache = None;
+        let limit = self.undo_limit;
+        self.current_buffer_mut().redo(limit)
+    }
+
+    pub fn ensure_cursor_visible(&mut self, term_w: u16, term_h: u16, config: &Config) {
+        let tab_bar_visible = config.tab_bar_visible(self.buffers.len());
         let buf = self.current_buffer_mut();
+        let cursor_col = editor::display_column(
+            &buf.lines[buf.cursor_y],
+            buf.cursor_x,
+            config.tab_display_width,
+        );
 
-        let sidebar_width = if show_line_numbers { 6 } else { 0 };
+        let sidebar_width = buf.gutter_width(config.show_line_numbers);
         let available_width = term_w.saturating_sub(sidebar_width) as usize;
-        let available_height = term_h.saturating_sub(
-            (if show_header { 1 } else { 0 })
-                + (if show_tab_bar { 1 } else { 0 })
-                + (if show_status_bar { 1 } else { 0 }),
-        ) as usize;
-
-        if buf.cursor_x < buf.viewport_offset_x {
-            buf.viewport_offset_x = buf.cursor_x;
-        } else if buf.cursor_x >= buf.viewport_offset_x + available_width {
-            buf.viewport_offset_x = buf
-                .cursor_x
-                .saturating_sub(available_width.saturating_sub(1));
+        let available_height = config.editor_height(term_h, tab_bar_visible);
+
+        if cursor_col < buf.viewport_offset_x {
+            buf.viewport_offset_x = cursor_col;
+        } else if cursor_col >= buf.viewport_offset_x + available_width {
+            buf.viewport_offset_x = cursor_col.saturating_sub(available_width.saturating_sub(1));
         }
 
         if buf.cursor_y < buf.viewport_offset_y {
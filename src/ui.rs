@@ -1,13 +1,22 @@
-use crate::state::{AppState, Config, MenuTab, Mode, Palette, PromptType, APP_NAME};
-use chrono::Local;
+use crate::editor::{self, MarkdownLine};
+use crate::state::{
+    help_visible_rows, AppState, Buffer, BufferInfo, Config, ConfirmType, CursorShape, KeyCombo,
+    MenuTab, Mode, Palette, PromptType, Selection, WipeConfirm, APP_NAME, COLOR_EDITOR_ROWS,
+    DIR_MARKER, FILE_MARKER, HELP_BINDINGS, HELP_CHROME_ROWS, MIN_EXPLORER_WIDTH, OUTLINE_ROWS,
+    TAB_NAME_MAX, WIPE_CONFIRM_LINES, WIPE_CONFIRM_WORD,
+};
+use chrono::{DateTime, Local};
 use crossterm::{
-    cursor::{Hide, MoveTo, Show},
+    cursor::{Hide, MoveTo, SetCursorStyle, Show},
+    event::{DisableBracketedPaste, DisableFocusChange},
     execute, queue,
     style::{Color, Print, SetBackgroundColor, SetForegroundColor},
-    terminal::{size, Clear, ClearType},
+    terminal::{disable_raw_mode, size, Clear, ClearType, LeaveAlternateScreen},
 };
 use std::collections::HashSet;
-use std::io::{Stdout, Write};
+use std::io::{stdout, Stdout, Write};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::LazyLock;
 
 static RUST_KEYWORDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
@@ -54,11 +63,8 @@ pub fn redraw_all(
     let (term_w, term_h) = size().unwrap_or((80, 24));
 
     let header_height = if config.show_header { 1 } else { 0 };
-    let tab_bar_height = if config.show_tab_bar && state.buffers.len() > 1 {
-        1
-    } else {
-        0
-    };
+    let tab_bar_visible = config.tab_bar_visible(state.buffers.len());
+    let tab_bar_height = if tab_bar_visible { 1 } else { 0 };
     let menu_height = if mode == Mode::Menu { 1 } else { 0 };
     let status_height = if config.show_status_bar { 1 } else { 0 };
 
@@ -67,31 +73,59 @@ pub fn redraw_all(
 
     queue!(
         stdout,
-        SetBackgroundColor(palette.ui_bg),
+        SetBackgroundColor(palette.ui_background),
         Clear(ClearType::Purge),
         MoveTo(0, 0)
     )?;
 
     if config.show_header {
-        draw_header(stdout, term_w, state, palette)?;
+        draw_header(stdout, term_w, state, config, palette)?;
     }
 
-    if config.show_tab_bar && state.buffers.len() > 1 {
-        draw_tab_bar(stdout, header_height, term_w, state, palette)?;
+    if tab_bar_visible {
+        draw_tab_bar(stdout, header_height, term_w, state, config, palette)?;
     }
 
     let buf = state.current_buffer();
-    let gutter_width = if config.show_line_numbers {
-        let max_lines = buf.lines.len().max(1);
-        (max_lines.to_string().len() + 2) as u16
-    } else {
-        0
-    };
+    let gutter_width = buf.gutter_width(config.show_line_numbers);
 
     let viewport_offset_y = buf.viewport_offset_y;
     let viewport_offset_x = buf.viewport_offset_x;
     let editor_width = term_w.saturating_sub(gutter_width) as usize;
 
+    let word_matches = if mode == Mode::Editing && !state.input_mode && state.selection.is_none() {
+        let line = &buf.lines[buf.cursor_y];
+        word_at(line, buf.cursor_x, &config.word_chars)
+            .map(|(start, end)| {
+                let visible = viewport_offset_y..viewport_offset_y + editor_height as usize;
+                let max_col = viewport_offset_x + editor_width;
+                word_occurrences(
+                    &buf.lines,
+                    visible,
+                    &line[start..end],
+                    max_col,
+                    config.tab_display_width,
+                    &config.word_chars,
+                )
+                    .into_iter()
+                    .filter(|&(y, s, e)| {
+                        !(y == buf.cursor_y && s <= buf.cursor_x && buf.cursor_x <= e)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let keywords = line_keywords(buf, config);
+    let markdown = markdown_enabled(buf, config).then(|| {
+        editor::markdown_lines(
+            &buf.lines,
+            viewport_offset_y..viewport_offset_y + editor_height as usize,
+        )
+    });
+
     for i in 0..editor_height {
         let screen_y = editor_start_y + i;
         let line_idx = viewport_offset_y + i as usize;
@@ -104,51 +138,135 @@ pub fn redraw_all(
         )?;
 
         if line_idx < buf.lines.len() {
-            if config.show_line_numbers {
-                let line_num = line_idx + 1;
-                let num_str = format!(
-                    " {:>width$} ",
-                    line_num,
-                    width = (gutter_width - 2) as usize
-                );
+            if gutter_width > 0 {
+                let num_str = format_line_number(line_idx + 1, gutter_width);
                 queue!(stdout, SetForegroundColor(palette.line_number_fg), Print(num_str))?;
             }
 
+            let highlight = match &markdown {
+                Some(kinds) => Highlight::Markdown(kinds[i as usize]),
+                None => keywords.map_or(Highlight::Plain, Highlight::Keywords),
+            };
             draw_line_with_selection(
                 stdout,
                 state,
                 config,
                 line_idx,
+                highlight,
                 viewport_offset_x,
                 editor_width,
-                palette,
             )?;
         }
     }
 
+    if let Some(column) = config.ruler_column {
+        if let Some(ruler_x) = ruler_screen_x(column, viewport_offset_x, editor_width) {
+            for i in 0..editor_height {
+                let line_idx = viewport_offset_y + i as usize;
+                let past_ruler = buf.lines.get(line_idx).is_some_and(|line| {
+                    editor::display_width_capped(line, config.tab_display_width, column + 1)
+                        > column
+                });
+                if past_ruler {
+                    continue;
+                }
+                queue!(
+                    stdout,
+                    MoveTo(gutter_width + ruler_x as u16, editor_start_y + i),
+                    SetBackgroundColor(palette.editor_background),
+                    SetForegroundColor(palette.ui_border),
+                    Print(config.glyphs("│"))
+                )?;
+            }
+        }
+    }
+
+    for (line_idx, start, end) in word_matches {
+        let word = &buf.lines[line_idx][start..end];
+        let col = editor::display_column(&buf.lines[line_idx], start, config.tab_display_width);
+        if col < viewport_offset_x
+            || col + editor::str_width(word) > viewport_offset_x + editor_width
+        {
+            continue;
+        }
+        queue!(
+            stdout,
+            MoveTo(
+                gutter_width + (col - viewport_offset_x) as u16,
+                editor_start_y + (line_idx - viewport_offset_y) as u16
+            ),
+            SetBackgroundColor(palette.match_highlight),
+            SetForegroundColor(palette.editor_foreground),
+            Print(word)
+        )?;
+    }
+
+    if editor_width > 0 {
+        for i in 0..editor_height {
+            let clipped = buf
+                .lines
+                .get(viewport_offset_y + i as usize)
+                .is_some_and(|line| {
+                    let cap = viewport_offset_x + editor_width + 1;
+                    let len = editor::display_width_capped(line, config.tab_display_width, cap);
+                    clipped_right(len, viewport_offset_x, editor_width)
+                });
+            if clipped {
+                queue!(
+                    stdout,
+                    MoveTo(gutter_width + editor_width as u16 - 1, editor_start_y + i),
+                    SetBackgroundColor(palette.editor_background),
+                    SetForegroundColor(palette.accent_secondary),
+                    Print(config.glyphs("»"))
+                )?;
+            }
+        }
+    }
+
+    if mode == Mode::Editing && state.show_empty_hint() {
+        draw_empty_hint(stdout, term_w, editor_start_y, editor_height, palette)?;
+    }
+
     if mode == Mode::Explorer {
-        draw_explorer(stdout, state, editor_start_y, editor_height, palette)?;
+        draw_explorer(
+            stdout,
+            state,
+            config,
+            editor_start_y,
+            editor_height,
+            term_w,
+            palette,
+        )?;
     }
     if mode == Mode::Help {
-        draw_help_overlay(stdout, term_w, term_h, state, palette)?;
+        draw_help_overlay(stdout, term_w, term_h, state, config, palette)?;
     }
     if mode == Mode::Settings {
         draw_settings_overlay(stdout, term_w, term_h, config, state.settings_idx, palette)?;
     }
     if mode == Mode::ColorEditor {
-        draw_color_editor_overlay(stdout, term_w, term_h, state, palette)?;
+        draw_color_editor_overlay(stdout, term_w, term_h, state, config, palette)?;
     }
     if mode == Mode::KeyRebind {
-        draw_key_rebind_overlay(stdout, term_w, term_h, state, palette)?;
+        draw_key_rebind_overlay(stdout, term_w, term_h, state, config, palette)?;
+    }
+    if mode == Mode::BufferInfo {
+        draw_buffer_info_overlay(stdout, term_w, term_h, state, config, palette)?;
+    }
+    if mode == Mode::Outline {
+        draw_outline_overlay(stdout, term_w, term_h, state, config, palette)?;
     }
     if mode == Mode::ConfirmWipe {
-        draw_confirm_wipe(stdout, term_w, term_h, palette)?;
+        draw_confirm_wipe(stdout, term_w, term_h, state, palette)?;
+    }
+    if mode == Mode::Confirm(ConfirmType::Overwrite) {
+        draw_confirm_overwrite(stdout, term_w, term_h, state, palette)?;
     }
     if state.input_mode {
-        draw_input_prompt(stdout, term_w, term_h, state, palette)?;
+        draw_input_prompt(stdout, term_w, term_h, state, config, palette)?;
     }
     if state.confirm_mode.is_some() {
-        draw_confirm_close_tab(stdout, term_w, term_h, state, palette)?;
+        draw_confirm_close_tab(stdout, term_w, term_h, state, config, palette)?;
     }
 
     if mode == Mode::Menu {
@@ -167,7 +285,12 @@ pub fn redraw_all(
     }
 
     if mode == Mode::Editing && !state.input_mode {
-        let cursor_x = gutter_width + (buf.cursor_x.saturating_sub(viewport_offset_x)) as u16;
+        let cursor_col = editor::display_column(
+            &buf.lines[buf.cursor_y],
+            buf.cursor_x,
+            config.tab_display_width,
+        );
+        let cursor_x = gutter_width + (cursor_col.saturating_sub(viewport_offset_x)) as u16;
         let cursor_y = editor_start_y + (buf.cursor_y.saturating_sub(viewport_offset_y)) as u16;
 
         if cursor_y < term_h.saturating_sub(status_height) {
@@ -175,94 +298,268 @@ pub fn redraw_all(
         }
     }
 
+    queue!(
+        stdout,
+        cursor_style(config.cursor_shape_for(mode, state.input_mode)),
+        Print(format!("\x1b]12;{}\x07", Palette::to_hex(palette.cursor)))
+    )?;
     execute!(stdout, Show)?;
     stdout.flush()
 }
 
+pub fn line_keywords(buf: &Buffer, config: &Config) -> Option<&'static HashSet<&'static str>> {
+    if buf.syntax_enabled(config.syntax_for_buffer(buf)) {
+        keywords_for(config.filetype_for(buf))
+    } else {
+        None
+    }
+}
+
+pub fn keywords_for(language: &str) -> Option<&'static HashSet<&'static str>> {
+    match language {
+        "rs" => Some(&*RUST_KEYWORDS),
+        "py" => Some(&*PYTHON_KEYWORDS),
+        "sh" | "bash" => Some(&*BASH_KEYWORDS),
+        _ => None,
+    }
+}
+
+fn cursor_style(shape: CursorShape) -> SetCursorStyle {
+    match shape {
+        CursorShape::Block => SetCursorStyle::SteadyBlock,
+        CursorShape::Bar => SetCursorStyle::SteadyBar,
+        CursorShape::Underline => SetCursorStyle::SteadyUnderScore,
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Highlight {
+    Plain,
+    Keywords(&'static HashSet<&'static str>),
+    Markdown(MarkdownLine),
+}
+
+fn markdown_enabled(buf: &Buffer, config: &Config) -> bool {
+    buf.syntax_enabled(config.syntax_for_buffer(buf)) && config.filetype_for(buf) == "md"
+}
+
 fn draw_line_with_selection(
     stdout: &mut Stdout,
     state: &AppState,
     config: &Config,
     line_idx: usize,
+    highlight: Highlight,
     viewport_offset_x: usize,
     editor_width: usize,
-    palette: Palette,
 ) -> std::io::Result<()> {
+    let palette = state.current_palette;
     let buf = state.current_buffer();
-    let line = &buf.lines[line_idx];
-    let file_ext = buf.filename.rsplit('.').next().unwrap_or("");
-
-    let keywords = if config.syntax_highlight {
-        match file_ext {
-            "rs" => Some(&*RUST_KEYWORDS),
-            "py" => Some(&*PYTHON_KEYWORDS),
-            "sh" | "bash" => Some(&*BASH_KEYWORDS),
-            _ => None,
+    let raw = &buf.lines[line_idx];
+    let line = editor::expand_tabs_until(
+        raw,
+        config.tab_display_width,
+        viewport_offset_x + editor_width,
+    );
+
+    let width = editor::str_width(&line);
+    let first_col = viewport_offset_x.min(width);
+    let end = editor::str_width(&line[..editor::column_to_byte(&line, first_col + editor_width)]);
+    let mut start = first_col;
+    if editor::str_width(&line[..editor::column_to_byte(&line, start)]) < start && start < end {
+        queue!(
+            stdout,
+            SetBackgroundColor(palette.editor_background),
+            Print(" ")
+        )?;
+        start = editor::str_width(&line[..editor::column_to_byte(&line, start + 1)]);
+    }
+    let overflow = config.ruler_column.filter(|_| config.highlight_overflow);
+
+    let overlap = state
+        .selection
+        .as_ref()
+        .and_then(|sel| selection_columns(sel, raw, line_idx, config.tab_display_width))
+        .map(|(sel_start, sel_end)| (sel_start.max(start), sel_end.min(end)))
+        .filter(|(overlap_start, overlap_end)| overlap_start < overlap_end);
+
+    if let Some((overlap_start, overlap_end)) = overlap {
+        if start < overlap_start {
+            render_unselected(
+                stdout,
+                &line,
+                start,
+                overlap_start,
+                overflow,
+                highlight,
+                &palette,
+            )?;
         }
-    } else {
-        None
-    };
 
-    let start = viewport_offset_x.min(line.len());
-    let end = (start + editor_width).min(line.len());
-
-    if let Some(sel) = &state.selection {
-        let (sx, sy, ex, ey) = sel.normalized();
-        if line_idx >= sy && line_idx <= ey {
-            let sel_start = if line_idx == sy { sx } else { 0 };
-            let sel_end = if line_idx == ey { ex } else { line.len() };
-
-            let overlap_start = sel_start.max(start);
-            let overlap_end = sel_end.min(end);
-
-            if overlap_start < overlap_end {
-                if start < overlap_start {
-                    render_text(
-                        stdout,
-                        &line[start..overlap_start],
-                        palette.editor_foreground,
-                        palette.editor_background,
-                        keywords,
-                        &palette,
-                    )?;
-                }
-
-                render_text(
-                    stdout,
-                    &line[overlap_start..overlap_end],
-                    palette.selection_fg,
-                    palette.selection_bg,
-                    None,
-                    &palette,
-                )?;
+        render_text(
+            stdout,
+            &line[editor::column_to_byte(&line, overlap_start)
+                ..editor::column_to_byte(&line, overlap_end)],
+            palette.selection_fg,
+            palette.selection_bg,
+            None,
+            &palette,
+        )?;
 
-                if overlap_end < end {
-                    render_text(
-                        stdout,
-                        &line[overlap_end..end],
-                        palette.editor_foreground,
-                        palette.editor_background,
-                        keywords,
-                        &palette,
-                    )?;
-                }
+        if overlap_end < end {
+            render_unselected(
+                stdout,
+                &line,
+                overlap_end,
+                end,
+                overflow,
+                highlight,
+                &palette,
+            )?;
+        }
+    } else if start < end {
+        render_unselected(stdout, &line, start, end, overflow, highlight, &palette)?;
+    }
 
-                return Ok(());
-            }
+    if let Some(sel) = &state.selection {
+        let tail = selection_tail_width(sel, line_idx, end - first_col, editor_width);
+        if tail > 0 {
+            queue!(
+                stdout,
+                SetBackgroundColor(palette.selection_bg),
+                Print(" ".repeat(tail))
+            )?;
         }
     }
 
-    if start < end {
-        render_text(
+    Ok(())
+}
+
+fn render_unselected(
+    stdout: &mut Stdout,
+    line: &str,
+    start: usize,
+    end: usize,
+    overflow: Option<usize>,
+    highlight: Highlight,
+    palette: &Palette,
+) -> std::io::Result<()> {
+    let split = overflow
+        .and_then(|column| overflow_span(start, end, column))
+        .map_or(end, |(overflow_start, _)| overflow_start);
+    let start = editor::column_to_byte(line, start);
+    let split = editor::column_to_byte(line, split);
+    let end = editor::column_to_byte(line, end);
+    let keywords = match highlight {
+        Highlight::Keywords(keywords) => Some(keywords),
+        _ => None,
+    };
+    match highlight {
+        Highlight::Markdown(kind) => render_markdown(stdout, line, start..split, kind, palette)?,
+        _ => render_text(
             stdout,
-            &line[start..end],
+            &line[start..split],
             palette.editor_foreground,
             palette.editor_background,
             keywords,
-            &palette,
-        )
+            palette,
+        )?,
+    }
+    render_text(
+        stdout,
+        &line[split..end],
+        palette.editor_background,
+        palette.warning,
+        keywords,
+        palette,
+    )
+}
+
+fn render_markdown(
+    stdout: &mut Stdout,
+    line: &str,
+    range: Range<usize>,
+    kind: MarkdownLine,
+    palette: &Palette,
+) -> std::io::Result<()> {
+    let fg = match kind {
+        MarkdownLine::Heading => palette.accent_primary,
+        MarkdownLine::Code => palette.syntax_comment,
+        MarkdownLine::Text => palette.editor_foreground,
+    };
+    let spans = if kind == MarkdownLine::Text {
+        editor::inline_code_spans(line)
+    } else {
+        Vec::new()
+    };
+
+    let mut pos = range.start;
+    for (span_start, span_end) in spans {
+        let span_start = span_start.clamp(pos, range.end);
+        let span_end = span_end.clamp(pos, range.end);
+        if span_start == span_end {
+            continue;
+        }
+        queue!(
+            stdout,
+            SetForegroundColor(fg),
+            SetBackgroundColor(palette.editor_background),
+            Print(&line[pos..span_start]),
+            SetForegroundColor(palette.syntax_string),
+            Print(&line[span_start..span_end])
+        )?;
+        pos = span_end;
+    }
+    queue!(
+        stdout,
+        SetForegroundColor(fg),
+        SetBackgroundColor(palette.editor_background),
+        Print(&line[pos..range.end])
+    )
+}
+
+pub fn clipped_right(line_len: usize, viewport_offset_x: usize, editor_width: usize) -> bool {
+    line_len > viewport_offset_x + editor_width
+}
+
+pub fn overflow_span(start: usize, end: usize, column: usize) -> Option<(usize, usize)> {
+    let overflow_start = start.max(column);
+    (overflow_start < end).then_some((overflow_start, end))
+}
+
+pub fn selection_columns(
+    sel: &Selection,
+    line: &str,
+    line_idx: usize,
+    tab_width: usize,
+) -> Option<(usize, usize)> {
+    let (sx, sy, ex, ey) = sel.normalized();
+    if line_idx < sy || line_idx > ey {
+        return None;
+    }
+    let start = if line_idx == sy { sx } else { 0 };
+    let end = if line_idx == ey { ex } else { line.len() };
+    Some((
+        editor::display_column(line, start, tab_width),
+        editor::display_column(line, end, tab_width),
+    ))
+}
+
+fn selection_tail_width(
+    sel: &Selection,
+    line_idx: usize,
+    visible_len: usize,
+    editor_width: usize,
+) -> usize {
+    let (sx, sy, _, ey) = sel.normalized();
+    if line_idx < sy || line_idx >= ey {
+        return 0;
+    }
+
+    let remaining = editor_width.saturating_sub(visible_len);
+    if line_idx > sy || sx == 0 {
+        remaining
     } else {
-        Ok(())
+        remaining.min(1)
     }
 }
 
@@ -343,7 +640,7 @@ fn render_word_wise(
     palette: &Palette,
 ) -> std::io::Result<()> {
     let mut last_end = 0;
-    for (start, end) in word_boundaries(text) {
+    for (start, end) in word_boundaries(text, "") {
         if start > last_end {
             queue!(
                 stdout,
@@ -356,7 +653,7 @@ fn render_word_wise(
         let word = &text[start..end];
         let color = if keywords.contains(word) {
             palette.syntax_keyword
-        } else if word.chars().next().map_or(false, |c| c.is_ascii_uppercase()) {
+        } else if word.chars().next().is_some_and(|c| c.is_ascii_uppercase()) {
             palette.syntax_type
         } else if word.parse::<f64>().is_ok() || word == "true" || word == "false" {
             palette.syntax_constant
@@ -387,12 +684,12 @@ fn render_word_wise(
     Ok(())
 }
 
-fn word_boundaries(s: &str) -> Vec<(usize, usize)> {
+fn word_boundaries(s: &str, extra: &str) -> Vec<(usize, usize)> {
     let mut bounds = Vec::new();
     let mut start = None;
 
     for (i, c) in s.char_indices() {
-        if c.is_alphanumeric() || c == '_' {
+        if editor::is_word_char(c, extra) {
             if start.is_none() {
                 start = Some(i);
             }
@@ -409,11 +706,113 @@ fn word_boundaries(s: &str) -> Vec<(usize, usize)> {
     bounds
 }
 
+pub fn first_visible_tab(widths: &[u16], active: usize, available: u16) -> usize {
+    let mut used = 0u16;
+    for (i, width) in widths.iter().enumerate().take(active + 1).rev() {
+        used = used.saturating_add(*width);
+        if used > available && i < active {
+            return i + 1;
+        }
+    }
+    0
+}
+
+pub fn tab_label(buf: &Buffer, config: &Config) -> String {
+    format!(
+        "{} {}",
+        config.modified_marker(buf.modified),
+        middle_ellipsis_with(&buf.filename, TAB_NAME_MAX, &config.glyphs("…"))
+    )
+}
+
+pub fn explorer_label(entry: &str, config: &Config) -> String {
+    for marker in [DIR_MARKER, FILE_MARKER] {
+        if let Some(name) = entry.strip_prefix(marker) {
+            return format!("{}{}", config.glyphs(marker), name);
+        }
+    }
+    entry.to_string()
+}
+
+pub fn middle_ellipsis(text: &str, max: usize) -> String {
+    middle_ellipsis_with(text, max, "…")
+}
+
+fn middle_ellipsis_with(text: &str, max: usize, ellipsis: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= max {
+        return text.to_string();
+    }
+    let keep = max.saturating_sub(ellipsis.chars().count());
+    if keep == 0 {
+        return ellipsis.chars().take(max).collect();
+    }
+    let tail = keep / 2;
+    let head = keep - tail;
+    let mut out: String = chars[..head].iter().collect();
+    out.push_str(ellipsis);
+    out.extend(&chars[chars.len() - tail..]);
+    out
+}
+
+pub fn explorer_width(configured: u16, term_w: u16) -> u16 {
+    configured.max(MIN_EXPLORER_WIDTH).min(term_w)
+}
+
+pub fn format_line_number(line_num: usize, gutter_width: u16) -> String {
+    let width = gutter_width.saturating_sub(2) as usize;
+    format!(" {:>width$} ", line_num, width = width)
+}
+
+pub fn ruler_screen_x(
+    column: usize,
+    viewport_offset_x: usize,
+    editor_width: usize,
+) -> Option<usize> {
+    column
+        .checked_sub(viewport_offset_x)
+        .filter(|&x| x < editor_width)
+}
+
+fn word_at(line: &str, x: usize, extra: &str) -> Option<(usize, usize)> {
+    let start = editor::word_start(line, x, extra);
+    let end = editor::word_end(line, x, extra);
+    (start < end).then_some((start, end))
+}
+
+pub fn word_occurrences(
+    lines: &[String],
+    visible: Range<usize>,
+    word: &str,
+    max_col: usize,
+    tab_width: usize,
+    extra: &str,
+) -> Vec<(usize, usize, usize)> {
+    let first = visible.start;
+    lines
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(visible.len())
+        .flat_map(|(line_idx, line)| {
+            let limit = editor::byte_at_display_column(line, max_col, tab_width);
+            let limit = line[limit..]
+                .find(|c: char| !editor::is_word_char(c, extra))
+                .map_or(line.len(), |i| limit + i);
+            word_boundaries(&line[..limit], extra)
+                .into_iter()
+                .filter(|&(start, end)| &line[start..end] == word)
+                .map(move |(start, end)| (line_idx, start, end))
+        })
+        .collect()
+}
+
 fn draw_tab_bar(
     stdout: &mut Stdout,
     y: u16,
     w: u16,
     state: &AppState,
+    config: &Config,
     palette: Palette,
 ) -> std::io::Result<()> {
     queue!(
@@ -426,11 +825,28 @@ fn draw_tab_bar(
 
     let mut x = 2u16;
     let active_idx = state.active_buffer;
+    let names: Vec<String> = state
+        .buffers
+        .iter()
+        .map(|buf| tab_label(buf, config))
+        .collect();
+    let widths: Vec<u16> = names
+        .iter()
+        .map(|name| name.chars().count() as u16 + 3)
+        .collect();
+    let first = first_visible_tab(&widths, active_idx, w.saturating_sub(7));
+
+    if first > 0 {
+        queue!(
+            stdout,
+            MoveTo(0, y),
+            SetForegroundColor(palette.accent_secondary),
+            Print(config.glyphs("‹"))
+        )?;
+    }
 
-    for (i, buf) in state.buffers.iter().enumerate() {
-        let marker = if buf.modified { "● " } else { "  " };
-        let name = format!("{}{}", marker, buf.filename);
-        let tab_len = name.len() as u16 + 2;
+    for (i, name) in names.iter().enumerate().skip(first) {
+        let tab_len = widths[i] - 1;
 
         if x + tab_len > w.saturating_sub(5) {
             queue!(
@@ -470,10 +886,16 @@ fn draw_header(
     stdout: &mut Stdout,
     w: u16,
     state: &AppState,
+    config: &Config,
     palette: Palette,
 ) -> std::io::Result<()> {
     let buf = state.current_buffer();
     let filename = &buf.filename;
+    let marker = if buf.modified {
+        format!(" {}", config.modified_marker(true))
+    } else {
+        String::new()
+    };
 
     queue!(
         stdout,
@@ -489,7 +911,12 @@ fn draw_header(
         SetForegroundColor(palette.header_fg),
         Print(format!("{} ", APP_NAME)),
         SetForegroundColor(palette.accent_secondary),
-        Print(format!("v0.2.0 — {}", filename))
+        Print(format!(
+            "{}{}{}",
+            config.glyphs("v0.2.0 — "),
+            filename,
+            marker
+        ))
     )?;
 
     let time = Local::now().format("%H:%M").to_string();
@@ -569,17 +996,36 @@ pub fn draw_current_dropdown(
                 " Next Tab ",
                 " Prev Tab ",
                 " Save As ",
+                " Rename ",
             ],
             10,
             14,
         ),
         MenuTab::Edit => (
-            vec![" Find ", " Replace ", " Go To Line ", " Wipe Buffer "],
+            vec![
+                " Find ",
+                " Replace ",
+                " Replace All ",
+                " Go To Line ",
+                " Wipe Buffer ",
+                " Tabs To Spaces ",
+                " Spaces To Tabs ",
+                " Outline ",
+                " Duplicate ",
+                " Align Indent ",
+            ],
             18,
-            15,
+            16,
         ),
         MenuTab::View => (
-            vec![" Header ", " Status ", " Lines ", " Tabs ", " Syntax "],
+            vec![
+                " Header ",
+                " Status ",
+                " Lines ",
+                " Tabs ",
+                " Syntax ",
+                " File Type ",
+            ],
             26,
             12,
         ),
@@ -628,13 +1074,21 @@ fn draw_input_prompt(
     w: u16,
     h: u16,
     state: &AppState,
+    config: &Config,
     palette: Palette,
 ) -> std::io::Result<()> {
     let title = match state.prompt_type {
         PromptType::SaveAs => "SAVE AS",
         PromptType::Find => "FIND TEXT",
+        PromptType::Replace => "REPLACE NEXT",
+        PromptType::ReplaceAll => "REPLACE ALL",
+        PromptType::ReplaceWith => "REPLACE WITH",
         PromptType::GoToLine => "GO TO LINE",
-        _ => "INPUT",
+        PromptType::Rename => "RENAME TO",
+        PromptType::ExportTheme => "EXPORT THEME AS",
+        PromptType::ImportTheme => "IMPORT THEME",
+        PromptType::AlignIndent => "ALIGN INDENT (COLUMNS, EMPTY = FIRST LINE)",
+        PromptType::FileType => "FILE TYPE (EMPTY = DETECT)",
     };
 
     let box_w = 50;
@@ -688,7 +1142,7 @@ fn draw_input_prompt(
         stdout,
         MoveTo(start_x + 2, start_y + 5),
         SetForegroundColor(palette.accent_secondary),
-        Print("[Enter confirm • Esc cancel]")
+        Print(config.glyphs("[Enter confirm • Esc cancel]"))
     )?;
 
     Ok(())
@@ -720,13 +1174,28 @@ fn draw_status_bar(
         format!(" {} ", format!("{:?}", mode).to_uppercase())
     };
 
-    let pos_str = format!(" L{},C{} ", buf.cursor_y + 1, buf.cursor_x + 1);
-    let modified = if buf.modified { " ●" } else { "" };
+    let column = editor::display_column(
+        &buf.lines[buf.cursor_y],
+        buf.cursor_x,
+        config.tab_display_width,
+    );
+    let pos_str = format!(" L{},C{} ", buf.cursor_y + 1, column + 1);
+    let modified = if buf.modified {
+        format!(" {}", config.modified_marker(true))
+    } else {
+        String::new()
+    };
     let auto_save = if config.auto_save { " AS" } else { "" };
-    let undo_redo = format!(" U:{} R:{}", state.undo_stack.len(), state.redo_stack.len());
+    let undo_redo = format!(" U:{} R:{}", buf.undo_depth(), buf.redo_stack.len());
+    let indent = format!(" {}", config.indent_for_buffer(buf).label());
+    let filetype = format!(" {}", config.filetype_for(buf));
+    let save_age = format!(" {}", format_save_age(buf.last_saved, Local::now()));
 
-    let right_str = format!("{}{}{}{}", pos_str, auto_save, modified, undo_redo);
-    let right_len = right_str.len() as u16;
+    let right_str = format!(
+        "{}{}{}{}{}{}{}",
+        pos_str, filetype, indent, auto_save, modified, save_age, undo_redo
+    );
+    let right_len = right_str.chars().count() as u16;
 
     queue!(
         stdout,
@@ -749,17 +1218,43 @@ fn draw_status_bar(
     Ok(())
 }
 
+pub fn format_save_age(last_saved: Option<DateTime<Local>>, now: DateTime<Local>) -> String {
+    let Some(saved) = last_saved else {
+        return "never saved".to_string();
+    };
+    let secs = (now - saved).num_seconds().max(0);
+    match secs {
+        0..=59 => format!("saved {}s ago", secs),
+        60..=3599 => format!("saved {}m ago", secs / 60),
+        3600..=86399 => format!("saved {}h ago", secs / 3600),
+        _ => format!("saved {}d ago", secs / 86400),
+    }
+}
+
 fn draw_help_overlay(
     stdout: &mut Stdout,
     w: u16,
     h: u16,
     state: &AppState,
+    config: &Config,
     palette: Palette,
 ) -> std::io::Result<()> {
+    let menu_key = KeyCombo::from_string(&config.menu_key)
+        .map_or_else(|| config.menu_key.clone(), |combo| combo.label());
+    let bindings: Vec<(&str, &str)> = [
+        ("Esc", "Menu / Close overlay"),
+        (menu_key.as_str(), "Menu (configurable)"),
+    ]
+    .into_iter()
+    .chain(HELP_BINDINGS.iter().copied())
+    .collect();
+
     let box_w = 60;
-    let box_h = 22;
+    let visible = help_visible_rows(h);
+    let box_h = ((visible + HELP_CHROME_ROWS) as u16).min(h);
     let x = (w.saturating_sub(box_w)) / 2;
     let y = (h.saturating_sub(box_h)) / 2;
+    let offset = state.help_offset.min(bindings.len() - visible);
 
     for i in 0..box_h {
         queue!(
@@ -777,21 +1272,7 @@ fn draw_help_overlay(
         Print("FERO HELP - KEYBINDINGS")
     )?;
 
-    let bindings = [
-        ("Esc", "Menu / Close overlay"),
-        ("Arrows", "Move cursor"),
-        ("Shift+Arrows", "Select text"),
-        ("Ctrl+A", "Select all"),
-        ("Ctrl+C/X/V", "Copy / Cut / Paste"),
-        ("Enter", "New line"),
-        ("Tab", "Indent"),
-        ("Ctrl+Tab", "Switch tab"),
-        ("Ctrl+S", "Save"),
-        ("Ctrl+Z/Y", "Undo / Redo"),
-        ("Ctrl+Up/Down", "Jump top/bottom"),
-    ];
-
-    for (i, (key, desc)) in bindings.iter().enumerate() {
+    for (i, (key, desc)) in bindings.iter().skip(offset).take(visible).enumerate() {
         queue!(stdout, MoveTo(x + 3, y + 3 + i as u16))?;
         queue!(stdout, SetForegroundColor(palette.accent_secondary), Print(key))?;
         queue!(stdout, MoveTo(x + 18, y + 3 + i as u16))?;
@@ -799,16 +1280,18 @@ fn draw_help_overlay(
     }
 
     if !state.keybind_state.custom_binds.is_empty() {
-        queue!(stdout, MoveTo(x + 3, y + 16))?;
+        let custom_y = y + 4 + visible as u16;
+        queue!(stdout, MoveTo(x + 3, custom_y))?;
         queue!(
             stdout,
             SetForegroundColor(palette.warning),
             Print("CUSTOM BINDS")
         )?;
 
-        let mut y_line = y + 17;
-        for (combo, action) in state.keybind_state.custom_binds.iter().take(3) {
-            let key_str = combo.to_string();
+        for (y_line, (combo, action)) in
+            (custom_y + 1..).zip(state.keybind_state.custom_binds.iter().take(3))
+        {
+            let key_str = KeyCombo::format_sequence(combo);
             queue!(stdout, MoveTo(x + 5, y_line))?;
             queue!(
                 stdout,
@@ -821,7 +1304,6 @@ fn draw_help_overlay(
                 SetForegroundColor(palette.ui_foreground),
                 Print(format!("{:?}", action))
             )?;
-            y_line += 1;
         }
     }
 
@@ -829,7 +1311,11 @@ fn draw_help_overlay(
         stdout,
         MoveTo(x + 2, y + box_h - 1),
         SetForegroundColor(palette.accent_secondary),
-        Print("Press Esc or Enter to close")
+        Print(if visible < bindings.len() {
+            config.glyphs("↑↓ scroll • Esc or Enter to close")
+        } else {
+            "Press Esc or Enter to close".into()
+        })
     )?;
 
     Ok(())
@@ -901,6 +1387,7 @@ fn draw_key_rebind_overlay(
     w: u16,
     h: u16,
     state: &AppState,
+    config: &Config,
     palette: Palette,
 ) -> std::io::Result<()> {
     let box_w = 52;
@@ -940,6 +1427,13 @@ fn draw_key_rebind_overlay(
         "Find",
         "Go To Line",
         "Wipe Buffer",
+        "Reveal in Explorer",
+        "Duplicate Selection",
+        "Toggle Buffer Syntax",
+        "Buffer Info",
+        "Find Next",
+        "Find Previous",
+        "Duplicate Line",
         "Reset to Default",
     ];
 
@@ -959,7 +1453,7 @@ fn draw_key_rebind_overlay(
                 stdout,
                 SetBackgroundColor(palette.accent_primary),
                 SetForegroundColor(palette.editor_background),
-                Print("▶ ")
+                Print(config.glyphs("▶ "))
             )?;
         } else {
             queue!(stdout, Print("  "))?;
@@ -986,20 +1480,26 @@ fn draw_key_rebind_overlay(
         queue!(
             stdout,
             SetForegroundColor(palette.warning),
-            Print("PRESS NEW KEY • Esc to cancel")
+            Print(config.glyphs("PRESS NEW KEY • Esc to cancel"))
         )?;
     } else {
         queue!(
             stdout,
             SetForegroundColor(palette.accent_secondary),
-            Print("↑↓ navigate • Enter rebind • Esc exit")
+            Print(config.glyphs("↑↓ navigate • Enter rebind • Esc exit"))
         )?;
     }
 
     Ok(())
 }
 
-fn draw_confirm_wipe(stdout: &mut Stdout, w: u16, h: u16, palette: Palette) -> std::io::Result<()> {
+fn draw_confirm_wipe(
+    stdout: &mut Stdout,
+    w: u16,
+    h: u16,
+    state: &AppState,
+    palette: Palette,
+) -> std::io::Result<()> {
     let box_w = 50;
     let box_h = 9;
     let x = (w.saturating_sub(box_w)) / 2;
@@ -1028,11 +1528,241 @@ fn draw_confirm_wipe(stdout: &mut Stdout, w: u16, h: u16, palette: Palette) -> s
         Print("This will permanently delete all text.")
     )?;
 
+    match state.current_buffer().wipe_confirm(WIPE_CONFIRM_LINES) {
+        WipeConfirm::Quick => queue!(
+            stdout,
+            MoveTo(x + 2, y + 5),
+            SetForegroundColor(palette.accent_primary),
+            Print("Press Y to confirm, any other key to cancel")
+        )?,
+        WipeConfirm::Typed => queue!(
+            stdout,
+            MoveTo(x + 2, y + 5),
+            SetForegroundColor(palette.accent_primary),
+            Print(format!(
+                "Type {} and press Enter to confirm",
+                WIPE_CONFIRM_WORD
+            )),
+            MoveTo(x + 2, y + 7),
+            SetForegroundColor(palette.ui_foreground),
+            Print(format!("> {}", state.wipe_input))
+        )?,
+    }
+
+    Ok(())
+}
+
+fn draw_empty_hint(
+    stdout: &mut Stdout,
+    w: u16,
+    y_start: u16,
+    height: u16,
+    palette: Palette,
+) -> std::io::Result<()> {
+    let hints = [
+        "Esc      open the menu",
+        "Ctrl+S   save",
+        "Ctrl+E   browse files",
+    ];
+    let box_w = hints.iter().map(|hint| hint.len()).max().unwrap_or(0) as u16;
+    let x = w.saturating_sub(box_w) / 2;
+    let y = y_start + height.saturating_sub(hints.len() as u16 + 2) / 2;
+
     queue!(
         stdout,
+        MoveTo(x, y),
+        SetBackgroundColor(palette.editor_background),
+        SetForegroundColor(palette.accent_primary),
+        Print(APP_NAME)
+    )?;
+    for (i, hint) in hints.iter().enumerate() {
+        queue!(
+            stdout,
+            MoveTo(x, y + 2 + i as u16),
+            SetForegroundColor(palette.line_number_fg),
+            Print(hint)
+        )?;
+    }
+
+    Ok(())
+}
+
+fn draw_confirm_overwrite(
+    stdout: &mut Stdout,
+    w: u16,
+    h: u16,
+    state: &AppState,
+    palette: Palette,
+) -> std::io::Result<()> {
+    let box_w = 50;
+    let box_h = 7;
+    let x = (w.saturating_sub(box_w)) / 2;
+    let y = (h.saturating_sub(box_h)) / 2;
+
+    for i in 0..box_h {
+        queue!(
+            stdout,
+            MoveTo(x, y + i),
+            SetBackgroundColor(palette.ui_background),
+            Print(" ".repeat(box_w as usize))
+        )?;
+    }
+
+    let target = state
+        .pending_rename
+        .as_ref()
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let max_len = (box_w - 4) as usize;
+    let target: String = target.chars().take(max_len).collect();
+
+    queue!(
+        stdout,
+        MoveTo(x + 2, y + 1),
+        SetForegroundColor(palette.warning),
+        Print("FILE EXISTS"),
+        MoveTo(x + 2, y + 3),
+        SetForegroundColor(palette.ui_foreground),
+        Print(target),
         MoveTo(x + 2, y + 5),
         SetForegroundColor(palette.accent_primary),
-        Print("Press Y to confirm, any other key to cancel")
+        Print("Press Y to overwrite, any other key to cancel")
+    )?;
+
+    Ok(())
+}
+
+fn draw_outline_overlay(
+    stdout: &mut Stdout,
+    w: u16,
+    h: u16,
+    state: &AppState,
+    config: &Config,
+    palette: Palette,
+) -> std::io::Result<()> {
+    let box_w = 60;
+    let rows = OUTLINE_ROWS.min(state.outline.len());
+    let box_h = (rows + 4).min(h as usize) as u16;
+    let x = (w.saturating_sub(box_w)) / 2;
+    let y = (h.saturating_sub(box_h)) / 2;
+    let visible = (box_h as usize).saturating_sub(4);
+
+    for i in 0..box_h {
+        queue!(
+            stdout,
+            MoveTo(x, y + i),
+            SetBackgroundColor(palette.ui_background),
+            Print(" ".repeat(box_w as usize))
+        )?;
+    }
+
+    queue!(
+        stdout,
+        MoveTo(x + 2, y + 1),
+        SetForegroundColor(palette.accent_primary),
+        Print("OUTLINE"),
+        MoveTo(x + 12, y + 1),
+        SetForegroundColor(palette.accent_secondary),
+        Print(config.glyphs("↑↓ navigate • Enter jump • Esc close"))
+    )?;
+
+    let entries = state.outline.iter().enumerate();
+    for (row, (i, symbol)) in entries.skip(state.outline_offset).take(visible).enumerate() {
+        let text: String = format!("{:>5}  {} {}", symbol.line + 1, symbol.kind, symbol.name)
+            .chars()
+            .take(box_w as usize - 6)
+            .collect();
+        queue!(stdout, MoveTo(x + 2, y + 3 + row as u16))?;
+        if i == state.outline_idx {
+            queue!(
+                stdout,
+                SetBackgroundColor(palette.accent_primary),
+                SetForegroundColor(palette.editor_background),
+                Print(format!("> {}", text))
+            )?;
+        } else {
+            queue!(
+                stdout,
+                SetBackgroundColor(palette.ui_background),
+                SetForegroundColor(palette.ui_foreground),
+                Print(format!("  {}", text))
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn draw_buffer_info_overlay(
+    stdout: &mut Stdout,
+    w: u16,
+    h: u16,
+    state: &AppState,
+    config: &Config,
+    palette: Palette,
+) -> std::io::Result<()> {
+    let info = BufferInfo::new(state.current_buffer(), state.selection.as_ref(), config);
+    let rows = [
+        ("Path", info.path),
+        ("Language", info.language),
+        ("Lines", info.lines.to_string()),
+        ("Words", info.words.to_string()),
+        ("Chars", info.chars.to_string()),
+        (
+            "Selection",
+            info.selected_chars
+                .map_or_else(|| "-".to_string(), |n| format!("{} chars", n)),
+        ),
+        (
+            "Cursor",
+            format!("Ln {}, Col {}", info.cursor_line, info.cursor_column),
+        ),
+        (
+            "Modified",
+            if info.modified { "yes" } else { "no" }.to_string(),
+        ),
+        ("Line ending", info.line_ending.to_string()),
+    ];
+
+    let box_w = 60;
+    let box_h = rows.len() as u16 + 5;
+    let x = (w.saturating_sub(box_w)) / 2;
+    let y = (h.saturating_sub(box_h)) / 2;
+
+    for i in 0..box_h {
+        queue!(
+            stdout,
+            MoveTo(x, y + i),
+            SetBackgroundColor(palette.ui_background),
+            Print(" ".repeat(box_w as usize))
+        )?;
+    }
+
+    queue!(
+        stdout,
+        MoveTo(x + 2, y + 1),
+        SetForegroundColor(palette.accent_primary),
+        Print("BUFFER INFO")
+    )?;
+
+    for (i, (label, value)) in rows.iter().enumerate() {
+        let value = middle_ellipsis(value, box_w as usize - 20);
+        queue!(
+            stdout,
+            MoveTo(x + 3, y + 3 + i as u16),
+            SetForegroundColor(palette.accent_secondary),
+            Print(label),
+            MoveTo(x + 17, y + 3 + i as u16),
+            SetForegroundColor(palette.ui_foreground),
+            Print(value)
+        )?;
+    }
+
+    queue!(
+        stdout,
+        MoveTo(x + 2, y + box_h - 1),
+        SetForegroundColor(palette.accent_secondary),
+        Print(config.glyphs("[Esc close]"))
     )?;
 
     Ok(())
@@ -1041,18 +1771,26 @@ fn draw_confirm_wipe(stdout: &mut Stdout, w: u16, h: u16, palette: Palette) -> s
 fn draw_explorer(
     stdout: &mut Stdout,
     state: &AppState,
+    config: &Config,
     y_start: u16,
     height: u16,
+    term_w: u16,
     palette: Palette,
 ) -> std::io::Result<()> {
-    let width = 40;
+    let width = explorer_width(config.explorer_width, term_w);
+    let title = if state.explorer_pending.is_some() {
+        config.glyphs(" FILE EXPLORER (LOADING…) ")
+    } else {
+        config.glyphs(" FILE EXPLORER ")
+    };
+    let name_w = (width as usize).saturating_sub(4);
 
     queue!(
         stdout,
         MoveTo(0, y_start),
         SetBackgroundColor(palette.ui_background),
         SetForegroundColor(palette.accent_primary),
-        Print(" FILE EXPLORER ")
+        Print(title.chars().take(width as usize).collect::<String>())
     )?;
 
     for i in 1..height {
@@ -1065,7 +1803,10 @@ fn draw_explorer(
 
         let file_idx = state.explorer_offset + (i as usize - 1);
         if file_idx < state.explorer_files.len() {
-            let name = &state.explorer_files[file_idx];
+            let name: String = explorer_label(&state.explorer_files[file_idx], config)
+                .chars()
+                .take(name_w)
+                .collect();
             queue!(stdout, MoveTo(2, y_start + i))?;
 
             if file_idx == state.explorer_idx {
@@ -1086,26 +1827,60 @@ fn draw_explorer(
         }
     }
 
+    let preview_x = width + 1;
+    let preview_w = term_w.saturating_sub(preview_x + 1) as usize;
+    if config.explorer_preview && preview_w >= 10 {
+        for i in 1..height {
+            let line = state
+                .explorer_preview
+                .get(i as usize - 1)
+                .map(String::as_str)
+                .unwrap_or("");
+            let clipped: String = line.chars().take(preview_w).collect();
+            let padding = preview_w - clipped.chars().count();
+            queue!(
+                stdout,
+                MoveTo(preview_x, y_start + i),
+                SetBackgroundColor(palette.editor_background),
+                SetForegroundColor(palette.line_number_fg),
+                Print(format!(" {}{}", clipped, " ".repeat(padding)))
+            )?;
+        }
+    }
+
     Ok(())
 }
 
+pub fn color_editor_window(
+    h: u16,
+    entries: usize,
+    idx: usize,
+    offset: usize,
+) -> (u16, usize, usize) {
+    let rows = COLOR_EDITOR_ROWS.min(entries);
+    let box_h = (rows + 5).min(h as usize) as u16;
+    let visible_items = (box_h as usize).saturating_sub(5);
+    let scroll_offset = offset.max((idx + 1).saturating_sub(visible_items));
+    (box_h, visible_items, scroll_offset)
+}
+
 fn draw_color_editor_overlay(
     stdout: &mut Stdout,
     w: u16,
     h: u16,
     state: &AppState,
+    config: &Config,
     palette: Palette,
 ) -> std::io::Result<()> {
     let box_w = 64;
-    let box_h = (state.color_entries.len() + 6).min(h as usize - 2) as u16;
+    let (box_h, visible_items, scroll_offset) = color_editor_window(
+        h,
+        state.color_entries.len(),
+        state.color_editor_idx,
+        state.color_editor_offset,
+    );
     let start_x = (w.saturating_sub(box_w)) / 2;
     let start_y = (h.saturating_sub(box_h)) / 2;
-    let visible_items = (box_h - 5) as usize;
-    let scroll_offset = if state.color_editor_idx >= visible_items {
-        state.color_editor_idx - visible_items + 1
-    } else {
-        0
-    };
 
     for i in 0..box_h {
         queue!(
@@ -1127,7 +1902,14 @@ fn draw_color_editor_overlay(
         stdout,
         MoveTo(start_x + 2, start_y + 2),
         SetForegroundColor(palette.accent_secondary),
-        Print("↑↓ navigate • Enter edit • Ctrl+S save • Esc exit")
+        Print(config.glyphs("↑↓ navigate • Enter edit • r reset • Ctrl+S save • Esc exit"))
+    )?;
+
+    queue!(
+        stdout,
+        MoveTo(start_x + 2, start_y + 3),
+        SetForegroundColor(palette.accent_secondary),
+        Print(config.glyphs("e export theme • i import theme"))
     )?;
 
     for (i, entry) in state.color_entries.iter().skip(scroll_offset).take(visible_items).enumerate() {
@@ -1135,7 +1917,11 @@ fn draw_color_editor_overlay(
         let global_idx = i + scroll_offset;
         queue!(stdout, MoveTo(start_x + 3, y))?;
 
-        let prefix = if global_idx == state.color_editor_idx { "▶ " } else { "  " };
+        let prefix = if global_idx == state.color_editor_idx {
+            config.glyphs("▶ ")
+        } else {
+            "  ".into()
+        };
         let name_fg = if global_idx == state.color_editor_idx { palette.accent_primary } else { palette.ui_foreground };
         let hex_fg = if global_idx == state.color_editor_idx && state.editing_hex { palette.syntax_string } else { palette.ui_foreground };
 
@@ -1152,7 +1938,7 @@ fn draw_color_editor_overlay(
         )?;
     }
 
-    if state.editing_hex {
+    if state.editing_hex && visible_items > 0 {
         let y = start_y + 4 + (state.color_editor_idx - scroll_offset) as u16;
         queue!(
             stdout,
@@ -1169,6 +1955,7 @@ fn draw_confirm_close_tab(
     w: u16,
     h: u16,
     state: &AppState,
+    config: &Config,
     palette: Palette,
 ) -> std::io::Result<()> {
     let box_w = 50;
@@ -1207,7 +1994,6 @@ fn draw_confirm_close_tab(
             MoveTo(x + 8, y + 5 + i as u16),
             SetBackgroundColor(if selected {
                 palette.accent_primary
-                palette.accent_primary
             } else {
                 palette.ui_background
             }),
@@ -1216,7 +2002,11 @@ fn draw_confirm_close_tab(
             } else {
                 palette.ui_foreground
             }),
-            Print(if selected { "▶ " } else { "  " }),
+            Print(if selected {
+                config.glyphs("▶ ")
+            } else {
+                "  ".into()
+            }),
             Print(opt)
         )?;
     }
@@ -1225,8 +2015,49 @@ fn draw_confirm_close_tab(
         stdout,
         MoveTo(x + 2, y + 8),
         SetForegroundColor(palette.accent_secondary),
-        Print("↑↓ navigate • Enter confirm • Esc cancel")
+        Print(config.glyphs("↑↓ navigate • Enter confirm • Esc cancel"))
     )?;
 
     Ok(())
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShutdownStep {
+    ResetCursorShape,
+    ResetCursorColor,
+    DisableFocusChange,
+    DisableBracketedPaste,
+    LeaveAlternateScreen,
+    DisableRawMode,
+}
+
+// Undo startup in reverse: raw mode was enabled before the alternate screen.
+pub const SHUTDOWN_SEQUENCE: &[ShutdownStep] = &[
+    ShutdownStep::ResetCursorShape,
+    ShutdownStep::ResetCursorColor,
+    ShutdownStep::DisableFocusChange,
+    ShutdownStep::DisableBracketedPaste,
+    ShutdownStep::LeaveAlternateScreen,
+    ShutdownStep::DisableRawMode,
+];
+
+pub fn restore_terminal_with(restored: &AtomicBool, mut run: impl FnMut(ShutdownStep)) {
+    if restored.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    for &step in SHUTDOWN_SEQUENCE {
+        run(step);
+    }
+}
+
+pub fn run_shutdown_step(step: ShutdownStep) -> std::io::Result<()> {
+    let mut stdout = stdout();
+    match step {
+        ShutdownStep::ResetCursorShape => execute!(stdout, SetCursorStyle::DefaultUserShape),
+        ShutdownStep::ResetCursorColor => execute!(stdout, Print("\x1b]112\x07")),
+        ShutdownStep::DisableFocusChange => execute!(stdout, DisableFocusChange),
+        ShutdownStep::DisableBracketedPaste => execute!(stdout, DisableBracketedPaste),
+        ShutdownStep::LeaveAlternateScreen => execute!(stdout, LeaveAlternateScreen),
+        ShutdownStep::DisableRawMode => disable_raw_mode(),
+    }
+}
@@ -0,0 +1,215 @@
+use fero::config;
+use fero::controller::load_custom_keybinds;
+use fero::state::{
+    AppState, Buffer, Config, CursorShape, KeyCombo, Mode, Palette, PaletteConfig, MAX_TAB_SIZE,
+    MIN_EXPLORER_WIDTH, MIN_UNDO_LIMIT, PALETTE_FIELDS,
+};
+
+#[test]
+fn cursor_shape_follows_mode() {
+    let mut config = Config::default();
+    assert_eq!(
+        config.cursor_shape_for(Mode::Editing, false),
+        CursorShape::Bar
+    );
+    assert_eq!(
+        config.cursor_shape_for(Mode::Menu, false),
+        CursorShape::Block
+    );
+    assert_eq!(
+        config.cursor_shape_for(Mode::Explorer, true),
+        CursorShape::Bar
+    );
+
+    config.insert_cursor = CursorShape::Underline;
+    config.overlay_cursor = CursorShape::Bar;
+    assert_eq!(
+        config.cursor_shape_for(Mode::Editing, false),
+        CursorShape::Underline
+    );
+    assert_eq!(config.cursor_shape_for(Mode::Help, false), CursorShape::Bar);
+}
+
+#[test]
+fn tab_bar_with_a_single_buffer() {
+    let mut config = Config::default();
+    assert!(!config.tab_bar_visible(1));
+    assert!(config.tab_bar_visible(2));
+
+    config.always_show_tab_bar = true;
+    assert!(config.tab_bar_visible(1));
+
+    config.show_tab_bar = false;
+    assert!(!config.tab_bar_visible(1));
+    assert!(!config.tab_bar_visible(3));
+}
+
+#[test]
+fn unnamed_buffers_survive_a_restart() {
+    let path = std::env::temp_dir().join(format!("fero-scratch-{}.toml", std::process::id()));
+
+    let mut app = AppState::new();
+    app.current_buffer_mut().lines = vec!["draft".to_string(), String::new(), "ideas".to_string()];
+    let mut named = Buffer::new("main.rs".to_string());
+    named.lines = vec!["fn main() {}".to_string()];
+    named.file_path = Some("main.rs".into());
+    app.add_buffer(named);
+    app.add_buffer(Buffer::new("empty".to_string()));
+
+    config::save_scratch(app.scratch_buffers(), &path).unwrap();
+    let scratch = config::load_scratch(&path);
+
+    let mut restored = AppState::new();
+    assert_eq!(restored.restore_scratch(scratch), 1);
+    assert_eq!(restored.buffers.len(), 1);
+    assert_eq!(restored.current_buffer().lines, ["draft", "", "ideas"]);
+    assert!(restored.current_buffer().modified);
+
+    config::save_scratch(Vec::new(), &path).unwrap();
+    assert!(!path.exists());
+    assert!(config::load_scratch(&path).is_empty());
+}
+
+#[test]
+fn corrupt_config_is_backed_up() {
+    let path = std::env::temp_dir().join(format!("fero-config-{}.toml", std::process::id()));
+    let backup = config::backup_path(&path);
+    std::fs::write(&path, "tab_size = \"four\"\nshow_header = tru").unwrap();
+
+    let (loaded, warning) = config::load_config_from(&path);
+    let saved = std::fs::read_to_string(&backup);
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(&backup);
+
+    assert!(warning.unwrap().starts_with("CONFIG INVALID"));
+    assert_eq!(loaded.tab_size, Config::default().tab_size);
+    assert_eq!(saved.unwrap(), "tab_size = \"four\"\nshow_header = tru");
+    assert_eq!(
+        backup.file_name().unwrap(),
+        format!("fero-config-{}.toml.bak", std::process::id()).as_str()
+    );
+
+    let (_, warning) = config::load_config_from(&path);
+    assert!(warning.is_none());
+}
+
+#[test]
+fn out_of_range_values_are_clamped() {
+    let path = std::env::temp_dir().join(format!("fero-clamp-{}.toml", std::process::id()));
+    let config = Config {
+        tab_size: 0,
+        tab_display_width: 400,
+        undo_limit: 1,
+        explorer_width: 2,
+        ruler_column: Some(0),
+        ..Config::default()
+    };
+    config::save_config_to(&config, &path).unwrap();
+
+    let (loaded, warning) = config::load_config_from(&path);
+    let _ = std::fs::remove_file(&path);
+
+    assert!(warning.is_none());
+    assert_eq!(loaded.tab_size, 1);
+    assert_eq!(loaded.tab_display_width, MAX_TAB_SIZE);
+    assert_eq!(loaded.undo_limit, MIN_UNDO_LIMIT);
+    assert_eq!(loaded.explorer_width, MIN_EXPLORER_WIDTH);
+    assert_eq!(loaded.ruler_column, None);
+}
+
+#[test]
+fn invalid_keybinds_are_dropped_and_counted() {
+    let mut app = AppState::new();
+    let mut config = Config {
+        custom_keybinds: vec![
+            (
+                "ALT|Char('d')".to_string(),
+                "DuplicateSelection".to_string(),
+            ),
+            ("ALT|Char('q')".to_string(), "Teleport".to_string()),
+        ],
+        ..Config::default()
+    };
+
+    assert_eq!(load_custom_keybinds(&mut app, &mut config), 1);
+    assert_eq!(app.keybind_state.custom_binds.len(), 1);
+    assert_eq!(config.custom_keybinds.len(), 1);
+}
+
+#[test]
+fn filetype_override_beats_extension_detection() {
+    let mut config = Config::default();
+    config
+        .extension_aliases
+        .insert("markdown".to_string(), "md".to_string());
+    let mut buf = Buffer::new("config".to_string());
+    assert_eq!(config.filetype_for(&buf), "plain");
+
+    buf.filetype_override = Some("toml".to_string());
+    assert_eq!(config.filetype_for(&buf), "toml");
+
+    buf.filename = "notes.txt".to_string();
+    buf.filetype_override = Some("markdown".to_string());
+    assert_eq!(config.filetype_for(&buf), "md");
+
+    config.language_syntax.insert("md".to_string(), false);
+    assert!(!config.syntax_for_buffer(&buf));
+    buf.filetype_override = None;
+    assert_eq!(config.filetype_for(&buf), "txt");
+    assert!(config.syntax_for_buffer(&buf));
+}
+
+#[test]
+fn filetype_override_indent_follows_extension_aliases() {
+    let mut config = Config::default();
+    config
+        .extension_aliases
+        .insert("tml".to_string(), "toml".to_string());
+    let mut buf = Buffer::new("config".to_string());
+    assert_eq!(config.indent_for_buffer(&buf).tab_size, config.tab_size);
+
+    buf.filetype_override = Some("tml".to_string());
+    let indent = config.indent_for_buffer(&buf);
+    assert_eq!(indent.tab_size, 2);
+    assert!(!indent.use_hard_tabs);
+}
+
+#[test]
+fn menu_key_labels_read_like_the_help_screen() {
+    let label = |s: &str| KeyCombo::from_string(s).unwrap().label();
+    assert_eq!(label(&Config::default().menu_key), "F10");
+    assert_eq!(label("ALT|Char('m')"), "Alt+M");
+    assert_eq!(label("CONTROL|PageDown"), "Ctrl+PageDown");
+}
+
+#[test]
+fn theme_names_stay_inside_the_themes_directory() {
+    let path = config::get_theme_path(" dark.toml ").unwrap();
+    assert!(path.ends_with("themes/dark.toml"));
+    assert_ne!(
+        config::get_theme_path("config"),
+        Some(config::get_config_path())
+    );
+
+    for name in ["", "   ", "../x", "..", "a/b", "a\\b", "/etc/passwd"] {
+        assert_eq!(config::get_theme_path(name), None, "{:?}", name);
+    }
+}
+
+#[test]
+fn color_entries_round_trip_through_the_palette() {
+    let mut app = AppState::new();
+    let mut original = PaletteConfig::default();
+    original.set("accent_primary", "#123456");
+    original.set("syntax_keyword", "#ABCDEF");
+    app.current_palette = Palette::from_config(&original);
+    app.populate_color_entries();
+    assert_eq!(app.color_entries.len(), PALETTE_FIELDS.len());
+
+    let palette = Palette::from_config(&PaletteConfig::from_entries(&app.color_entries));
+    let round_trip = palette.to_config();
+    for name in PALETTE_FIELDS {
+        assert_eq!(round_trip.get(name), original.get(name), "{}", name);
+    }
+    assert_eq!(round_trip.get("accent_primary"), Some("#123456"));
+}
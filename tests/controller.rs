@@ -0,0 +1,1445 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use fero::controller::{
+    change_explorer_dir, finish_frame, flashes_as_unbound, focus_lost_saves, handle_key_event,
+    handle_paste_event, load_more_explorer, open_file, open_startup_arg, overlay_action,
+    parse_file_arg, save_on_focus_lost, OverlayAction,
+};
+use fero::editor;
+use fero::state::{
+    AppState, Buffer, BufferInfo, ChordOutcome, Config, ConfirmChoice, ConfirmType, KeyCombo,
+    KeybindAction, KeybindState, MenuTab, Mode, PaletteConfig, PromptType, COLOR_EDITOR_ROWS,
+    DEFAULT_KEYBINDS, DIR_MARKER, EXPLORER_BATCH, FILE_MARKER,
+};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+struct Session {
+    app: AppState,
+    mode: Mode,
+    active_tab: MenuTab,
+    dropdown_idx: usize,
+    config: Config,
+}
+
+impl Session {
+    fn new() -> Self {
+        Self {
+            app: AppState::new(),
+            mode: Mode::Editing,
+            active_tab: MenuTab::Re,
+            dropdown_idx: 0,
+            config: Config::default(),
+        }
+    }
+
+    fn press(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        handle_key_event(
+            &mut self.app,
+            KeyEvent::new(code, modifiers),
+            &mut self.mode,
+            &mut self.active_tab,
+            &mut self.dropdown_idx,
+            &mut self.config,
+        )
+        .unwrap()
+    }
+
+    fn key(&mut self, code: KeyCode) {
+        self.press(code, KeyModifiers::NONE);
+    }
+
+    fn ctrl(&mut self, c: char) {
+        self.press(KeyCode::Char(c), KeyModifiers::CONTROL);
+    }
+
+    fn type_str(&mut self, text: &str) {
+        for c in text.chars() {
+            match c {
+                '\n' => self.key(KeyCode::Enter),
+                _ => self.key(KeyCode::Char(c)),
+            }
+        }
+    }
+
+    fn bind(&mut self, c: char, action: KeybindAction) {
+        let combo = KeyCombo {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::ALT,
+        };
+        self.app
+            .keybind_state
+            .custom_binds
+            .insert(vec![combo], action);
+    }
+
+    fn alt(&mut self, c: char) {
+        self.press(KeyCode::Char(c), KeyModifiers::ALT);
+    }
+
+    fn lines(&self) -> &[String] {
+        &self.app.current_buffer().lines
+    }
+
+    fn cursor(&self) -> (usize, usize) {
+        let buf = self.app.current_buffer();
+        (buf.cursor_x, buf.cursor_y)
+    }
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("fero-{}-{}.txt", name, std::process::id()))
+}
+
+#[test]
+fn typing_and_arrows_edit_the_buffer() {
+    let mut s = Session::new();
+    s.type_str("hello\nworld");
+    assert_eq!(s.lines(), ["hello", "world"]);
+    assert_eq!(s.cursor(), (5, 1));
+
+    s.key(KeyCode::Up);
+    s.key(KeyCode::Left);
+    s.key(KeyCode::Left);
+    s.type_str("!");
+    assert_eq!(s.lines(), ["hel!lo", "world"]);
+
+    s.key(KeyCode::Down);
+    s.key(KeyCode::Backspace);
+    assert_eq!(s.lines(), ["hel!lo", "word"]);
+    assert!(s.app.current_buffer().modified);
+}
+
+#[test]
+fn undo_and_redo_through_keys() {
+    let mut s = Session::new();
+    s.type_str("ab\ncd");
+
+    s.ctrl('z');
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["ab", ""]);
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["ab"]);
+    assert_eq!(s.cursor(), (2, 0));
+
+    s.ctrl('y');
+    s.ctrl('y');
+    assert_eq!(s.lines(), ["ab", "c"]);
+
+    s.type_str("x");
+    s.ctrl('y');
+    assert_eq!(s.lines(), ["ab", "cx"]);
+}
+
+#[test]
+fn typing_save_and_reopen_round_trip() {
+    let path = temp_path("roundtrip");
+    let mut s = Session::new();
+    s.app.current_buffer_mut().file_path = Some(path.clone());
+
+    s.type_str("fn main() {\n    println!(\"hi\");\n}");
+    s.ctrl('s');
+
+    assert!(!s.app.current_buffer().modified);
+    let reopened = editor::load_from_file(path.to_str().unwrap());
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(reopened.unwrap(), s.lines());
+}
+
+#[test]
+fn buffer_info_reports_the_loaded_line_ending() {
+    let path = temp_path("crlf");
+    std::fs::write(&path, "one\r\ntwo\r\n").unwrap();
+    let mut s = Session::new();
+    open_file(&mut s.app, path.clone(), &s.config).unwrap();
+    assert_eq!(s.lines(), ["one", "two"]);
+    let info = BufferInfo::new(s.app.current_buffer(), None, &s.config);
+    assert_eq!(info.line_ending, "CRLF");
+
+    s.ctrl('s');
+    let written = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(written.unwrap(), "one\ntwo\n");
+    let info = BufferInfo::new(s.app.current_buffer(), None, &s.config);
+    assert_eq!(info.line_ending, "LF");
+}
+
+#[test]
+fn escape_opens_the_menu() {
+    let mut s = Session::new();
+    s.key(KeyCode::Esc);
+    assert_eq!(s.mode, Mode::Menu);
+}
+
+#[test]
+fn backspace_in_indentation_returns_to_tab_stop() {
+    let mut s = Session::new();
+    s.config.tab_size = 4;
+    s.type_str("      x");
+    for _ in 0..7 {
+        s.key(KeyCode::Left);
+    }
+    for _ in 0..6 {
+        s.key(KeyCode::Right);
+    }
+
+    s.key(KeyCode::Backspace);
+    assert_eq!(s.lines(), ["    x"]);
+    assert_eq!(s.cursor(), (4, 0));
+
+    s.key(KeyCode::Backspace);
+    assert_eq!(s.lines(), ["x"]);
+    assert_eq!(s.cursor(), (0, 0));
+
+    s.type_str("ab  ");
+    s.key(KeyCode::Backspace);
+    assert_eq!(s.lines(), ["ab x"]);
+}
+
+#[test]
+fn duplicate_selection_on_one_line() {
+    let mut s = Session::new();
+    s.bind('d', KeybindAction::DuplicateSelection);
+    s.type_str("let ab = 1;");
+    for _ in 0..7 {
+        s.key(KeyCode::Left);
+    }
+    s.press(KeyCode::Right, KeyModifiers::SHIFT);
+    s.press(KeyCode::Right, KeyModifiers::SHIFT);
+
+    s.alt('d');
+    assert_eq!(s.lines(), ["let abab = 1;"]);
+    assert_eq!(s.cursor(), (8, 0));
+    assert_eq!(s.app.selection.as_ref().unwrap().normalized(), (6, 0, 8, 0));
+}
+
+#[test]
+fn duplicate_selection_across_lines() {
+    let mut s = Session::new();
+    s.bind('d', KeybindAction::DuplicateSelection);
+    s.type_str("one\ntwo\nend");
+    s.key(KeyCode::Up);
+    s.key(KeyCode::Up);
+    s.key(KeyCode::Left);
+    s.press(KeyCode::Down, KeyModifiers::SHIFT);
+    s.press(KeyCode::Down, KeyModifiers::SHIFT);
+
+    s.alt('d');
+    assert_eq!(s.lines(), ["one", "two", "ene", "two", "end"]);
+    assert_eq!(s.app.selection.as_ref().unwrap().normalized(), (2, 2, 2, 4));
+}
+
+#[test]
+fn duplicate_without_selection_copies_the_line() {
+    let mut s = Session::new();
+    s.bind('d', KeybindAction::DuplicateSelection);
+    s.type_str("abc\nxyz");
+    s.key(KeyCode::Up);
+    s.alt('d');
+    assert_eq!(s.lines(), ["abc", "abc", "xyz"]);
+    assert_eq!(s.cursor(), (3, 1));
+}
+
+#[test]
+fn overlay_keys_dispatch_per_mode() {
+    assert_eq!(
+        overlay_action(Mode::Help, KeyCode::Enter),
+        Some(OverlayAction::Close)
+    );
+    assert_eq!(
+        overlay_action(Mode::Settings, KeyCode::Enter),
+        Some(OverlayAction::Confirm)
+    );
+    assert_eq!(
+        overlay_action(Mode::Explorer, KeyCode::Esc),
+        Some(OverlayAction::Close)
+    );
+    assert_eq!(overlay_action(Mode::Outline, KeyCode::Up), None);
+    assert_eq!(overlay_action(Mode::Editing, KeyCode::Esc), None);
+
+    let mut s = Session::new();
+    s.mode = Mode::Explorer;
+    s.key(KeyCode::Esc);
+    assert_eq!(s.mode, Mode::Menu);
+    s.key(KeyCode::Esc);
+    assert_eq!(s.mode, Mode::Editing);
+}
+
+#[test]
+fn unreadable_directory_keeps_the_current_one() {
+    let mut s = Session::new();
+    let start = s.app.current_dir.clone();
+    let missing = std::env::temp_dir().join(format!("fero-missing-{}", std::process::id()));
+
+    assert!(!change_explorer_dir(&mut s.app, missing, &s.config));
+    assert_eq!(s.app.current_dir, start);
+    assert_eq!(s.app.status_flash.as_deref(), Some("DIRECTORY MISSING"));
+
+    let tmp = std::env::temp_dir();
+    assert!(change_explorer_dir(&mut s.app, tmp.clone(), &s.config));
+    assert_eq!(s.app.current_dir, tmp);
+}
+
+#[test]
+fn large_directories_load_in_batches() {
+    let dir = std::env::temp_dir().join(format!("fero-bigdir-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let total = EXPLORER_BATCH * 2 + 10;
+    for i in 0..total {
+        std::fs::write(dir.join(format!("f{:05}.txt", i)), "").unwrap();
+    }
+    std::fs::write(dir.join("README.txt"), "").unwrap();
+    std::fs::create_dir_all(dir.join("Zdir")).unwrap();
+
+    let mut s = Session::new();
+    assert!(change_explorer_dir(&mut s.app, dir.clone(), &s.config));
+    let first_batch = s.app.explorer_files.len();
+    assert!(s.app.explorer_pending.is_some());
+
+    s.app.explorer_idx = 3;
+    let selected = s.app.explorer_files[3].clone();
+    while load_more_explorer(&mut s.app) {}
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(first_batch, EXPLORER_BATCH);
+    assert_eq!(s.app.explorer_files.len(), total + 2);
+    assert_eq!(s.app.explorer_files[0], format!("{}Zdir", DIR_MARKER));
+    let files = &s.app.explorer_files[1..];
+    assert!(files
+        .windows(2)
+        .all(|w| w[0].to_lowercase() < w[1].to_lowercase()));
+    assert_eq!(files.last(), Some(&format!("{}README.txt", FILE_MARKER)));
+    assert_eq!(s.app.explorer_files[s.app.explorer_idx], selected);
+}
+
+#[test]
+fn flash_clear_tick_leaves_the_viewport_alone() {
+    let mut s = Session::new();
+    s.type_str(&"line\n".repeat(200));
+    let buf = s.app.current_buffer_mut();
+    buf.viewport_offset_y = 40;
+    buf.viewport_offset_x = 3;
+
+    s.app.flash_status("SAVED".to_string());
+    let mut redraw = false;
+    for _ in 0..20 {
+        redraw |= finish_frame(&mut s.app, &s.config, false, false);
+    }
+
+    assert!(redraw);
+    assert!(s.app.status_flash.is_none());
+    let buf = s.app.current_buffer();
+    assert_eq!((buf.viewport_offset_x, buf.viewport_offset_y), (3, 40));
+    assert_eq!(s.cursor(), (0, 200));
+
+    assert!(!finish_frame(&mut s.app, &s.config, false, true));
+    assert_eq!(s.app.current_buffer().viewport_offset_y, 40);
+    assert!(finish_frame(&mut s.app, &s.config, true, true));
+    let buf = s.app.current_buffer();
+    assert_eq!(buf.viewport_offset_x, 0);
+    assert!(buf.viewport_offset_y > 40);
+}
+
+#[test]
+fn idle_ticks_redraw_when_the_save_age_changes() {
+    let mut s = Session::new();
+    let saved = chrono::Local::now() - chrono::Duration::seconds(90);
+    s.app.current_buffer_mut().last_saved = Some(saved);
+    assert!(finish_frame(&mut s.app, &s.config, false, false));
+    assert!(!finish_frame(&mut s.app, &s.config, false, false));
+
+    s.app.current_buffer_mut().last_saved = Some(saved - chrono::Duration::hours(2));
+    assert!(finish_frame(&mut s.app, &s.config, false, false));
+
+    s.config.show_status_bar = false;
+    s.app.current_buffer_mut().last_saved = None;
+    assert!(!finish_frame(&mut s.app, &s.config, false, false));
+}
+
+#[test]
+fn color_editor_scrolls_to_keep_the_selection_in_view() {
+    let mut s = color_editor_session();
+    let last = s.app.color_entries.len() - 1;
+    for _ in 0..=last + 2 {
+        s.key(KeyCode::Down);
+    }
+    assert_eq!(s.app.color_editor_idx, last);
+    assert_eq!(s.app.color_editor_offset, last + 1 - COLOR_EDITOR_ROWS);
+
+    for _ in 0..COLOR_EDITOR_ROWS {
+        s.key(KeyCode::Up);
+    }
+    assert_eq!(s.app.color_editor_idx, last - COLOR_EDITOR_ROWS);
+    assert_eq!(s.app.color_editor_offset, last - COLOR_EDITOR_ROWS);
+}
+
+#[cfg(feature = "grapheme")]
+#[test]
+fn arrows_and_backspace_step_over_grapheme_clusters() {
+    let accent = "e\u{301}";
+    let flag = "\u{1F1F3}\u{1F1F4}";
+    let line = format!("a{}b{}c", accent, flag);
+
+    let mut s = Session::new();
+    s.app.current_buffer_mut().lines = vec![line.clone()];
+    s.key(KeyCode::Right);
+    s.key(KeyCode::Right);
+    assert_eq!(s.cursor(), (1 + accent.len(), 0));
+    s.key(KeyCode::Right);
+    s.key(KeyCode::Right);
+    assert_eq!(s.cursor(), (2 + accent.len() + flag.len(), 0));
+
+    s.key(KeyCode::Left);
+    s.key(KeyCode::Backspace);
+    assert_eq!(s.lines(), [format!("a{}{}c", accent, flag)]);
+    s.key(KeyCode::Backspace);
+    assert_eq!(s.lines(), [format!("a{}c", flag)]);
+    assert_eq!(s.cursor(), (1, 0));
+
+    s.key(KeyCode::Right);
+    s.key(KeyCode::Backspace);
+    assert_eq!(s.lines(), ["ac"]);
+}
+
+#[test]
+fn file_args_carry_an_optional_position() {
+    assert_eq!(
+        parse_file_arg("src/main.rs:120"),
+        (PathBuf::from("src/main.rs"), Some(120), None)
+    );
+    assert_eq!(
+        parse_file_arg("src/main.rs:120:8"),
+        (PathBuf::from("src/main.rs"), Some(120), Some(8))
+    );
+    assert_eq!(
+        parse_file_arg("notes.txt"),
+        (PathBuf::from("notes.txt"), None, None)
+    );
+    assert_eq!(
+        parse_file_arg("notes.txt:abc"),
+        (PathBuf::from("notes.txt:abc"), None, None)
+    );
+    assert_eq!(
+        parse_file_arg("notes.txt:0:4"),
+        (PathBuf::from("notes.txt:0:4"), None, None)
+    );
+    assert_eq!(
+        parse_file_arg("notes:draft.txt"),
+        (PathBuf::from("notes:draft.txt"), None, None)
+    );
+    assert_eq!(
+        parse_file_arg("notes:draft.txt:3"),
+        (PathBuf::from("notes:draft.txt"), Some(3), None)
+    );
+    assert_eq!(
+        parse_file_arg("dir:v2/notes.txt:3"),
+        (PathBuf::from("dir:v2/notes.txt"), Some(3), None)
+    );
+}
+
+#[test]
+fn editing_invalidates_cached_find_matches() {
+    let mut s = Session::new();
+    s.bind('f', KeybindAction::Find);
+    s.type_str("one ab\ntwo\nab ab");
+    s.press(KeyCode::Up, KeyModifiers::CONTROL);
+
+    s.alt('f');
+    s.type_str("ab");
+    s.key(KeyCode::Enter);
+    let cache = s.app.find_cache.clone().unwrap();
+    assert_eq!(cache.query, "ab");
+    assert_eq!(cache.matches, [(0, 4), (2, 0), (2, 3)]);
+    assert_eq!(s.cursor(), (4, 0));
+
+    s.type_str("x");
+    assert!(s.app.find_cache.is_none());
+
+    s.alt('f');
+    s.type_str("ab");
+    s.key(KeyCode::Enter);
+    assert_eq!(
+        s.app.find_cache.as_ref().unwrap().matches,
+        [(0, 5), (2, 0), (2, 3)]
+    );
+    s.ctrl('z');
+    assert!(s.app.find_cache.is_none());
+}
+
+#[test]
+fn find_stops_when_cancelled() {
+    let lines = vec!["needle".to_string(); editor::FIND_CANCEL_INTERVAL * 3];
+    assert_eq!(
+        editor::find_matches(&lines, "needle", || false).map(|m| m.len()),
+        Some(lines.len())
+    );
+    assert_eq!(editor::find_matches(&lines, "needle", || true), None);
+}
+
+#[test]
+fn focus_loss_saves_only_modified_named_buffers() {
+    let path = temp_path("focus");
+    let mut s = Session::new();
+    s.type_str("scratch");
+    s.app.add_buffer(Buffer::new("clean.txt".to_string()));
+    s.app.current_buffer_mut().file_path = Some(temp_path("focus-clean"));
+    let mut named = Buffer::new("notes.txt".to_string());
+    named.lines = vec!["hello".to_string()];
+    named.file_path = Some(path.clone());
+    named.modified = true;
+    s.app.add_buffer(named);
+
+    assert!(focus_lost_saves(&s.app, &s.config).is_empty());
+
+    s.config.save_on_focus_lost = true;
+    assert_eq!(focus_lost_saves(&s.app, &s.config), [2]);
+    assert_eq!(save_on_focus_lost(&mut s.app, &s.config), 1);
+    let written = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(written.unwrap(), "hello\n");
+    assert!(!s.app.buffers[2].modified);
+    assert!(s.app.buffers[0].modified);
+    assert!(focus_lost_saves(&s.app, &s.config).is_empty());
+}
+
+#[test]
+fn bracketed_paste_inserts_verbatim_in_one_undo_step() {
+    let mut s = Session::new();
+    s.type_str("    fn main() {}");
+    s.key(KeyCode::Left);
+
+    let pasted = "\n        let x = 1;\r\n\tif x {\n    ";
+    handle_paste_event(&mut s.app, pasted, s.mode);
+    assert_eq!(
+        s.lines(),
+        ["    fn main() {", "        let x = 1;", "\tif x {", "    }"]
+    );
+    assert_eq!(s.cursor(), (4, 3));
+
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["    fn main() {}"]);
+}
+
+#[test]
+fn bracketed_paste_replaces_the_selection() {
+    let mut s = Session::new();
+    s.type_str("let old = 1;");
+    for _ in 0..5 {
+        s.key(KeyCode::Left);
+    }
+    for _ in 0..3 {
+        s.press(KeyCode::Left, KeyModifiers::SHIFT);
+    }
+
+    handle_paste_event(&mut s.app, "new", s.mode);
+    assert_eq!(s.lines(), ["let new = 1;"]);
+    assert_eq!(s.cursor(), (7, 0));
+    assert!(s.app.selection.is_none());
+
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["let old = 1;"]);
+}
+
+#[test]
+fn switching_buffers_scrolls_to_the_stored_cursor() {
+    let mut s = Session::new();
+    s.app.open_new_buffer(&s.config);
+    let buf = s.app.current_buffer_mut();
+    buf.lines = vec!["line".to_string(); 500];
+    buf.cursor_y = 450;
+    buf.viewport_offset_y = 0;
+    s.app.switch_buffer(0);
+
+    s.bind('n', KeybindAction::NextTab);
+    s.alt('n');
+    let buf = s.app.current_buffer();
+    assert_eq!(s.app.active_buffer, 1);
+    assert!(buf.viewport_offset_y <= 450 && 450 < buf.viewport_offset_y + 24);
+
+    s.app.current_buffer_mut().viewport_offset_y = 0;
+    s.press(KeyCode::Tab, KeyModifiers::CONTROL);
+    s.press(KeyCode::Tab, KeyModifiers::CONTROL);
+    let buf = s.app.current_buffer();
+    assert_eq!(s.app.active_buffer, 1);
+    assert!(buf.viewport_offset_y <= 450 && 450 < buf.viewport_offset_y + 24);
+}
+
+#[test]
+fn unbound_modifier_combos_flash_instead_of_typing() {
+    assert!(flashes_as_unbound(
+        KeyCode::Char('k'),
+        KeyModifiers::CONTROL
+    ));
+    assert!(flashes_as_unbound(KeyCode::Char('q'), KeyModifiers::ALT));
+    assert!(!flashes_as_unbound(KeyCode::Char('k'), KeyModifiers::NONE));
+    assert!(!flashes_as_unbound(KeyCode::Char('K'), KeyModifiers::SHIFT));
+    assert!(!flashes_as_unbound(
+        KeyCode::Char('@'),
+        KeyModifiers::CONTROL | KeyModifiers::ALT
+    ));
+    assert!(!flashes_as_unbound(KeyCode::F(5), KeyModifiers::CONTROL));
+
+    let mut s = Session::new();
+    s.ctrl('k');
+    assert_eq!(s.lines(), [""]);
+    assert_eq!(s.app.status_flash.as_deref(), Some("UNBOUND: Ctrl+K"));
+}
+
+#[test]
+fn leaving_an_edited_line_trims_its_trailing_whitespace() {
+    let mut s = Session::new();
+    s.config.trim_edited_lines = true;
+    s.app.current_buffer_mut().lines = vec![
+        "keep  ".to_string(),
+        "edit".to_string(),
+        "also  ".to_string(),
+    ];
+    s.key(KeyCode::Down);
+    for _ in 0..4 {
+        s.key(KeyCode::Right);
+    }
+    s.type_str("ed  ");
+    assert_eq!(s.lines()[1], "edited  ");
+
+    s.key(KeyCode::Down);
+    assert_eq!(s.lines(), ["keep  ", "edited", "also  "]);
+
+    s.key(KeyCode::Up);
+    s.key(KeyCode::Up);
+    assert_eq!(s.lines(), ["keep  ", "edited", "also  "]);
+}
+
+#[test]
+fn moved_edited_lines_keep_their_trim_mark() {
+    let mut s = Session::new();
+    s.config.trim_edited_lines = true;
+    s.app.current_buffer_mut().lines = ["a", "b  ", "c", "d  "].map(String::from).to_vec();
+    s.key(KeyCode::Down);
+    s.key(KeyCode::Down);
+    s.key(KeyCode::End);
+    s.type_str("  ");
+    s.press(KeyCode::Up, KeyModifiers::ALT);
+    assert_eq!(s.lines(), ["a", "c  ", "b  ", "d  "]);
+
+    s.key(KeyCode::Down);
+    s.key(KeyCode::Down);
+    assert_eq!(s.lines(), ["a", "c", "b  ", "d  "]);
+    assert_eq!(s.cursor().1, 3);
+}
+
+#[test]
+fn shift_home_and_end_select_to_the_line_edges() {
+    let mut s = Session::new();
+    s.type_str("hello world");
+    s.key(KeyCode::Home);
+    for _ in 0..6 {
+        s.key(KeyCode::Right);
+    }
+
+    s.press(KeyCode::End, KeyModifiers::SHIFT);
+    assert_eq!(s.cursor(), (11, 0));
+    let sel = s.app.selection.as_ref().unwrap();
+    assert_eq!(sel.normalized(), (6, 0, 11, 0));
+
+    s.key(KeyCode::Left);
+    s.press(KeyCode::Home, KeyModifiers::SHIFT);
+    assert_eq!(s.cursor(), (0, 0));
+    let sel = s.app.selection.as_ref().unwrap();
+    assert_eq!(sel.normalized(), (0, 0, 10, 0));
+}
+
+#[test]
+fn wipe_confirm_wipes_on_y_and_cancels_otherwise() {
+    let mut s = Session::new();
+    s.type_str("draft");
+    s.app.current_buffer_mut().modified = false;
+
+    s.bind('w', KeybindAction::WipeBuffer);
+    s.alt('w');
+    assert_eq!(s.mode, Mode::ConfirmWipe);
+    s.key(KeyCode::Char('n'));
+    assert_eq!(s.mode, Mode::Editing);
+    assert_eq!(s.lines(), ["draft"]);
+    assert_eq!(s.app.status_flash.as_deref(), Some("WIPE CANCELLED"));
+
+    s.alt('w');
+    s.key(KeyCode::Char('y'));
+    assert_eq!(s.mode, Mode::Editing);
+    assert_eq!(s.lines(), [""]);
+
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["draft"]);
+}
+
+#[test]
+fn cursor_steps_over_multibyte_characters() {
+    let mut s = Session::new();
+    s.type_str("café 日本");
+    assert_eq!(s.lines(), ["café 日本"]);
+    assert_eq!(s.cursor(), ("café 日本".len(), 0));
+
+    s.key(KeyCode::Left);
+    s.key(KeyCode::Backspace);
+    assert_eq!(s.lines(), ["café 本"]);
+    s.key(KeyCode::Home);
+    for _ in 0..4 {
+        s.key(KeyCode::Right);
+    }
+    s.key(KeyCode::Backspace);
+    s.type_str("è");
+    assert_eq!(s.lines(), ["cafè 本"]);
+
+    s.key(KeyCode::End);
+    s.type_str("\nxxxxxxxx");
+    s.key(KeyCode::Home);
+    for _ in 0..6 {
+        s.key(KeyCode::Right);
+    }
+    s.key(KeyCode::Up);
+    assert_eq!(s.cursor(), ("cafè ".len(), 0));
+    s.type_str("日");
+    assert_eq!(s.lines()[0], "cafè 日本");
+}
+
+#[test]
+fn configured_menu_key_opens_the_menu() {
+    let mut s = Session::new();
+    s.key(KeyCode::F(10));
+    assert_eq!(s.mode, Mode::Menu);
+
+    let mut s = Session::new();
+    s.config.menu_key = "ALT|Char('m')".to_string();
+    s.alt('m');
+    assert_eq!(s.mode, Mode::Menu);
+    assert_eq!(s.lines(), [""]);
+
+    let mut s = Session::new();
+    s.config.menu_key = "ALT|Char('m')".to_string();
+    s.key(KeyCode::F(10));
+    assert_eq!(s.mode, Mode::Editing);
+}
+
+#[test]
+fn find_lands_on_a_char_boundary_after_multibyte_text() {
+    let mut s = Session::new();
+    s.type_str("first\n日本語 hello");
+    s.press(KeyCode::Up, KeyModifiers::CONTROL);
+
+    s.bind('f', KeybindAction::Find);
+    s.alt('f');
+    s.type_str("hello");
+    s.key(KeyCode::Enter);
+    assert_eq!(s.cursor(), ("日本語 ".len(), 1));
+
+    s.key(KeyCode::Backspace);
+    s.key(KeyCode::Right);
+    s.type_str("H");
+    assert_eq!(s.lines()[1], "日本語hHello");
+}
+
+#[test]
+fn find_next_and_previous_cycle_with_wrap() {
+    let mut s = Session::new();
+    s.type_str("foo bar\nbar\nbaz bar");
+    s.press(KeyCode::Up, KeyModifiers::CONTROL);
+
+    s.key(KeyCode::F(3));
+    assert_eq!(s.app.status_flash.as_deref(), Some("NO PREVIOUS SEARCH"));
+
+    s.bind('f', KeybindAction::Find);
+    s.alt('f');
+    s.type_str("bar");
+    s.key(KeyCode::Enter);
+    assert_eq!(s.cursor(), (4, 0));
+
+    s.key(KeyCode::F(3));
+    assert_eq!(s.cursor(), (0, 1));
+    s.key(KeyCode::F(3));
+    assert_eq!(s.cursor(), (4, 2));
+    s.key(KeyCode::F(3));
+    assert_eq!(s.cursor(), (4, 0));
+
+    s.press(KeyCode::F(3), KeyModifiers::SHIFT);
+    assert_eq!(s.cursor(), (4, 2));
+    s.press(KeyCode::F(3), KeyModifiers::SHIFT);
+    assert_eq!(s.cursor(), (0, 1));
+}
+
+#[test]
+fn every_action_has_a_default_binding() {
+    let actions = (0..).map_while(KeybindAction::from_index);
+    for action in actions.filter(|&a| a != KeybindAction::ResetToDefault) {
+        assert!(
+            DEFAULT_KEYBINDS.iter().any(|&(_, bound)| bound == action),
+            "{:?} has no default binding",
+            action
+        );
+    }
+    for (i, (combo, _)) in DEFAULT_KEYBINDS.iter().enumerate() {
+        assert!(!DEFAULT_KEYBINDS[i + 1..].iter().any(|(c, _)| c == combo));
+    }
+
+    let mut s = Session::new();
+    s.ctrl('f');
+    assert!(s.app.input_mode);
+    s.key(KeyCode::Esc);
+    s.key(KeyCode::Esc);
+    assert_eq!(s.mode, Mode::Menu);
+}
+
+#[test]
+fn replace_prompts_for_both_terms_and_undoes_in_one_step() {
+    let mut s = Session::new();
+    s.type_str("cat dog\ncat cat");
+    s.press(KeyCode::Up, KeyModifiers::CONTROL);
+    s.key(KeyCode::Right);
+
+    s.key(KeyCode::Esc);
+    s.dropdown_idx = 1;
+    s.active_tab = MenuTab::Edit;
+    s.key(KeyCode::Enter);
+    s.type_str("cat");
+    s.key(KeyCode::Enter);
+    assert!(s.app.input_mode);
+    s.type_str("cow");
+    s.key(KeyCode::Enter);
+    assert!(!s.app.input_mode);
+    assert_eq!(s.lines(), ["cat dog", "cow cat"]);
+    assert_eq!(s.cursor(), (3, 1));
+
+    s.key(KeyCode::Esc);
+    s.dropdown_idx = 2;
+    s.active_tab = MenuTab::Edit;
+    s.key(KeyCode::Enter);
+    s.type_str("cat");
+    s.key(KeyCode::Enter);
+    s.type_str("owl");
+    s.key(KeyCode::Enter);
+    assert_eq!(s.lines(), ["owl dog", "cow owl"]);
+    assert_eq!(s.app.status_flash.as_deref(), Some("REPLACED 2"));
+
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["cat dog", "cow cat"]);
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["cat dog", "cat cat"]);
+}
+
+#[test]
+fn outline_follows_the_resolved_file_type() {
+    fn outline(s: &mut Session) -> Vec<String> {
+        s.key(KeyCode::Esc);
+        s.dropdown_idx = 7;
+        s.active_tab = MenuTab::Edit;
+        s.key(KeyCode::Enter);
+        s.app.outline.iter().map(|sym| sym.name.clone()).collect()
+    }
+
+    let mut s = Session::new();
+    s.app.current_buffer_mut().filename = "notes.txt".to_string();
+    s.app.current_buffer_mut().lines = vec!["def greet(name):".to_string()];
+    s.app.current_buffer_mut().filetype_override = Some("py".to_string());
+    assert_eq!(outline(&mut s), ["greet"]);
+    assert_eq!(s.mode, Mode::Outline);
+
+    let mut s = Session::new();
+    s.app.current_buffer_mut().filename = "stubs.pyi".to_string();
+    s.app.current_buffer_mut().lines = vec!["class Greeter:".to_string()];
+    assert_eq!(outline(&mut s), ["Greeter"]);
+}
+
+#[test]
+fn home_toggles_between_indent_and_line_start() {
+    let mut s = Session::new();
+    s.app.current_buffer_mut().lines = vec!["    let x = 1;".to_string()];
+    s.key(KeyCode::End);
+    s.key(KeyCode::Home);
+    assert_eq!(s.cursor(), (4, 0));
+    s.key(KeyCode::Home);
+    assert_eq!(s.cursor(), (0, 0));
+    s.key(KeyCode::Home);
+    assert_eq!(s.cursor(), (4, 0));
+
+    s.key(KeyCode::End);
+    s.press(KeyCode::Home, KeyModifiers::SHIFT);
+    let sel = s.app.selection.as_ref().unwrap();
+    assert_eq!(sel.normalized(), (4, 0, 14, 0));
+}
+
+#[test]
+fn page_keys_move_by_a_screen_and_extend_selection() {
+    let mut s = Session::new();
+    s.app.current_buffer_mut().lines = (0..500).map(|i| format!("line {}", i)).collect();
+    s.key(KeyCode::End);
+
+    s.key(KeyCode::PageDown);
+    let (x, page) = s.cursor();
+    assert!(page > 0);
+    assert_eq!(x, "line 0".len());
+    assert!(s.app.current_buffer().viewport_offset_y > 0);
+
+    s.press(KeyCode::PageDown, KeyModifiers::SHIFT);
+    assert_eq!(s.cursor().1, page * 2);
+    let sel = s.app.selection.as_ref().unwrap();
+    assert_eq!(sel.normalized(), (6, page, 6, page * 2));
+
+    s.key(KeyCode::PageUp);
+    assert!(s.app.selection.is_none());
+    s.key(KeyCode::PageUp);
+    s.key(KeyCode::PageUp);
+    assert_eq!(s.cursor().1, 0);
+    assert_eq!(s.app.current_buffer().viewport_offset_y, 0);
+
+    for _ in 0..100 {
+        s.key(KeyCode::PageDown);
+    }
+    assert_eq!(s.cursor(), (6, 499));
+}
+
+#[test]
+fn missing_file_argument_opens_a_new_named_buffer() {
+    let path = temp_path("startup-new");
+    let arg = format!("{}:10", path.display());
+    let mut s = Session::new();
+    s.app.buffers.clear();
+    assert_eq!(open_startup_arg(&mut s.app, &arg, &s.config), None);
+    assert_eq!(s.app.buffers.len(), 1);
+    assert_eq!(s.app.current_buffer().file_path.as_ref(), Some(&path));
+    assert_eq!(s.app.status_flash.as_deref(), Some("NEW FILE"));
+
+    s.type_str("fresh");
+    s.ctrl('s');
+    let written = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(written.unwrap(), "fresh\n");
+}
+
+#[test]
+fn startup_column_counts_characters() {
+    let path = temp_path("startup-column");
+    std::fs::write(&path, "héllo wörld\n").unwrap();
+    let arg = format!("{}:1:8", path.display());
+    let mut s = Session::new();
+    open_startup_arg(&mut s.app, &arg, &s.config);
+    let _ = std::fs::remove_file(&path);
+
+    let buf = s.app.current_buffer();
+    assert_eq!(&buf.lines[0][buf.cursor_x..], "örld");
+}
+
+#[test]
+fn directory_argument_starts_in_the_explorer() {
+    let dir = std::env::temp_dir().join(format!("fero-startup-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("notes.txt"), "hi\n").unwrap();
+
+    let config = Config::default();
+    let mut app = AppState::new();
+    app.buffers.clear();
+    let mode = open_startup_arg(&mut app, dir.to_str().unwrap(), &config);
+    let current_dir = app.current_dir.clone();
+    let files = app.explorer_files.clone();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(mode, Some(Mode::Explorer));
+    assert_eq!(
+        current_dir,
+        std::fs::canonicalize(std::env::temp_dir())
+            .unwrap()
+            .join(dir.file_name().unwrap())
+    );
+    assert!(files.iter().any(|f| f.ends_with("notes.txt")));
+    assert!(app.buffers.is_empty());
+}
+
+#[test]
+fn ctrl_backspace_deletes_the_previous_word() {
+    let mut s = Session::new();
+    s.type_str("foo bar");
+    s.press(KeyCode::Backspace, KeyModifiers::CONTROL);
+    assert_eq!(s.lines(), ["foo "]);
+    assert_eq!(s.cursor(), (4, 0));
+
+    s.type_str("\n");
+    s.press(KeyCode::Backspace, KeyModifiers::CONTROL);
+    assert_eq!(s.lines(), ["foo "]);
+    assert_eq!(s.cursor(), (4, 0));
+
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["foo ", ""]);
+    s.ctrl('z');
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["foo bar"]);
+}
+
+#[test]
+fn ctrl_d_duplicates_the_current_line() {
+    let mut s = Session::new();
+    s.type_str("abc\nxyz");
+    s.key(KeyCode::Up);
+    s.key(KeyCode::Left);
+    s.ctrl('d');
+    assert_eq!(s.lines(), ["abc", "abc", "xyz"]);
+    assert_eq!(s.cursor(), (2, 1));
+    assert!(s.app.current_buffer().modified);
+
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["abc", "xyz"]);
+}
+
+#[test]
+fn ctrl_d_duplicates_a_selected_block() {
+    let mut s = Session::new();
+    s.type_str("one\ntwo\nend");
+    s.key(KeyCode::Up);
+    s.key(KeyCode::Up);
+    s.press(KeyCode::Down, KeyModifiers::SHIFT);
+    s.ctrl('d');
+    assert_eq!(s.lines(), ["one", "two", "one", "two", "end"]);
+    assert_eq!(s.cursor(), (3, 3));
+    assert_eq!(s.app.selection.as_ref().unwrap().normalized(), (3, 2, 3, 3));
+}
+
+#[test]
+fn bracket_wraps_the_selection() {
+    let mut s = Session::new();
+    s.type_str("call a + b");
+    for _ in 0..5 {
+        s.press(KeyCode::Left, KeyModifiers::SHIFT);
+    }
+    s.type_str("(");
+    assert_eq!(s.lines(), ["call (a + b)"]);
+    assert_eq!(
+        s.app.selection.as_ref().unwrap().normalized(),
+        (6, 0, 11, 0)
+    );
+
+    s.type_str("[");
+    assert_eq!(s.lines(), ["call ([a + b])"]);
+
+    s.ctrl('z');
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["call a + b"]);
+}
+
+#[test]
+fn quote_wraps_a_multiline_selection() {
+    let mut s = Session::new();
+    s.type_str("one\ntwo");
+    s.key(KeyCode::Up);
+    s.key(KeyCode::Home);
+    s.press(KeyCode::Down, KeyModifiers::SHIFT);
+    s.press(KeyCode::End, KeyModifiers::SHIFT);
+    s.type_str("\"");
+    assert_eq!(s.lines(), ["\"one", "two\""]);
+    assert_eq!(s.app.selection.as_ref().unwrap().normalized(), (1, 0, 3, 1));
+    assert_eq!(s.cursor(), (3, 1));
+}
+
+#[test]
+fn alt_arrows_move_the_current_line() {
+    let mut s = Session::new();
+    s.type_str("one\ntwo\nthree");
+    s.key(KeyCode::Up);
+    s.press(KeyCode::Up, KeyModifiers::ALT);
+    assert_eq!(s.lines(), ["two", "one", "three"]);
+    assert_eq!(s.cursor(), (3, 0));
+
+    s.press(KeyCode::Up, KeyModifiers::ALT);
+    assert_eq!(s.lines(), ["two", "one", "three"]);
+
+    s.press(KeyCode::Down, KeyModifiers::ALT);
+    s.press(KeyCode::Down, KeyModifiers::ALT);
+    s.press(KeyCode::Down, KeyModifiers::ALT);
+    assert_eq!(s.lines(), ["one", "three", "two"]);
+    assert_eq!(s.cursor(), (3, 2));
+
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["one", "two", "three"]);
+}
+
+#[test]
+fn alt_arrows_move_the_selected_block() {
+    let mut s = Session::new();
+    s.type_str("a\nb\nc\nd");
+    s.key(KeyCode::Up);
+    s.key(KeyCode::Up);
+    s.press(KeyCode::Down, KeyModifiers::SHIFT);
+    s.press(KeyCode::Up, KeyModifiers::ALT);
+    assert_eq!(s.lines(), ["b", "c", "a", "d"]);
+    assert_eq!(s.app.selection.as_ref().unwrap().normalized(), (1, 0, 1, 1));
+    assert_eq!(s.cursor(), (1, 1));
+
+    s.press(KeyCode::Down, KeyModifiers::ALT);
+    s.press(KeyCode::Down, KeyModifiers::ALT);
+    assert_eq!(s.lines(), ["a", "d", "b", "c"]);
+    assert_eq!(s.app.selection.as_ref().unwrap().normalized(), (1, 2, 1, 3));
+}
+
+#[test]
+fn typing_replaces_the_selection() {
+    let mut s = Session::new();
+    s.type_str("let value = 1;");
+    s.key(KeyCode::Home);
+    for _ in 0..4 {
+        s.key(KeyCode::Right);
+    }
+    for _ in 0..5 {
+        s.press(KeyCode::Right, KeyModifiers::SHIFT);
+    }
+    s.type_str("x");
+    assert_eq!(s.lines(), ["let x = 1;"]);
+    assert_eq!(s.cursor(), (5, 0));
+    assert!(s.app.selection.is_none());
+
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["let value = 1;"]);
+
+    s.press(KeyCode::End, KeyModifiers::SHIFT);
+    s.key(KeyCode::Enter);
+    assert_eq!(s.lines(), ["let value", ""]);
+}
+
+#[test]
+fn tab_indents_a_multiline_selection() {
+    let mut s = Session::new();
+    s.config.tab_size = 4;
+    s.type_str("a\n\nb");
+    s.key(KeyCode::Up);
+    s.key(KeyCode::Up);
+    s.press(KeyCode::Down, KeyModifiers::SHIFT);
+    s.press(KeyCode::Down, KeyModifiers::SHIFT);
+    s.key(KeyCode::Tab);
+    assert_eq!(s.lines(), ["    a", "", "    b"]);
+    assert_eq!(s.app.selection.as_ref().unwrap().normalized(), (0, 0, 0, 2));
+    assert_eq!(s.cursor(), (0, 2));
+
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["a", "", "b"]);
+}
+
+#[test]
+fn shift_tab_dedents_a_multiline_selection() {
+    let mut s = Session::new();
+    s.config.tab_size = 4;
+    s.type_str("      one\n  two\nthree");
+    s.key(KeyCode::Up);
+    s.key(KeyCode::Up);
+    s.press(KeyCode::Down, KeyModifiers::SHIFT);
+    s.press(KeyCode::Down, KeyModifiers::SHIFT);
+    s.press(KeyCode::BackTab, KeyModifiers::SHIFT);
+    assert_eq!(s.lines(), ["  one", "two", "three"]);
+    assert_eq!(s.app.selection.as_ref().unwrap().normalized(), (1, 0, 5, 2));
+
+    s.press(KeyCode::BackTab, KeyModifiers::SHIFT);
+    assert_eq!(s.lines(), ["one", "two", "three"]);
+    assert_eq!(s.app.selection.as_ref().unwrap().normalized(), (0, 0, 5, 2));
+}
+
+#[test]
+fn saved_buffers_are_not_kept_as_scratch() {
+    let path = temp_path("saved-scratch");
+    let mut s = Session::new();
+    s.app.current_buffer_mut().filename = path.to_string_lossy().to_string();
+    s.type_str("keep me");
+    assert_eq!(s.app.scratch_buffers().len(), 1);
+
+    s.ctrl('s');
+    let scratch = s.app.scratch_buffers();
+    let _ = std::fs::remove_file(&path);
+    assert!(scratch.is_empty());
+    assert_eq!(s.app.current_buffer().file_path.as_ref(), Some(&path));
+}
+
+#[test]
+fn trimming_a_left_line_is_undoable_and_resets_find() {
+    let mut s = Session::new();
+    s.config.trim_edited_lines = true;
+    s.app.current_buffer_mut().lines = vec!["x".to_string(), "y".to_string()];
+    s.key(KeyCode::End);
+    s.type_str("    ");
+    s.ctrl('f');
+    s.type_str("  ");
+    s.key(KeyCode::Enter);
+    assert_eq!(s.cursor(), (1, 0));
+
+    s.key(KeyCode::Down);
+    assert_eq!(s.lines(), ["x", "y"]);
+    s.press(KeyCode::F(3), KeyModifiers::SHIFT);
+    assert_eq!(s.app.status_flash.as_deref(), Some("NOT FOUND:   "));
+    assert_eq!(s.cursor(), (1, 1));
+    s.type_str("!");
+    assert_eq!(s.lines(), ["x", "y!"]);
+
+    s.ctrl('z');
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["x    ", "y"]);
+}
+
+#[test]
+fn empty_find_leaves_the_cursor_alone() {
+    let mut s = Session::new();
+    s.type_str("one\ntwo");
+    s.key(KeyCode::Left);
+    s.ctrl('f');
+    s.key(KeyCode::Enter);
+    assert!(!s.app.input_mode);
+    assert_eq!(s.cursor(), (2, 1));
+    assert_eq!(s.app.last_search, None);
+}
+
+#[test]
+fn explorer_preview_is_not_read_when_disabled() {
+    let dir = std::env::temp_dir().join(format!("fero-preview-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.txt"), "hello\n").unwrap();
+
+    let mut s = Session::new();
+    assert!(change_explorer_dir(&mut s.app, dir.clone(), &s.config));
+    let shown = s.app.explorer_preview.clone();
+    s.config.explorer_preview = false;
+    assert!(change_explorer_dir(&mut s.app, dir.clone(), &s.config));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(shown, ["hello"]);
+    assert!(s.app.explorer_preview.is_empty());
+}
+
+#[test]
+fn focus_loss_saves_save_as_buffers_and_trims_them() {
+    let path = temp_path("focus-save-as");
+    let mut s = Session::new();
+    s.config.save_on_focus_lost = true;
+    s.config.ensure_final_newline = true;
+    s.type_str("draft");
+    s.app.input_mode = true;
+    s.app.prompt_type = PromptType::SaveAs;
+    s.app.input_buffer = path.to_string_lossy().to_string();
+    s.key(KeyCode::Enter);
+    assert!(!s.app.current_buffer().modified);
+
+    s.type_str("\n\n");
+    assert_eq!(save_on_focus_lost(&mut s.app, &s.config), 1);
+    let written = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(written.unwrap(), "draft\n");
+    assert_eq!(s.lines(), ["draft"]);
+    assert_eq!(s.cursor(), (0, 0));
+    s.ctrl('z');
+    assert_eq!(s.lines(), ["draft", "", ""]);
+}
+
+#[test]
+fn renaming_an_unnamed_buffer_leaves_a_same_named_file_alone() {
+    let stray = temp_path("rename-stray");
+    let target = temp_path("rename-target");
+    std::fs::write(&stray, "unrelated\n").unwrap();
+    let mut s = Session::new();
+    s.app.current_buffer_mut().filename = stray.to_string_lossy().to_string();
+    s.type_str("draft");
+    s.app.input_mode = true;
+    s.app.prompt_type = PromptType::Rename;
+    s.app.input_buffer = target.to_string_lossy().to_string();
+    s.key(KeyCode::Enter);
+    let stray_text = std::fs::read_to_string(&stray);
+    let target_text = std::fs::read_to_string(&target);
+
+    let mut other = Session::new();
+    other.type_str("second draft");
+    other.app.input_mode = true;
+    other.app.prompt_type = PromptType::Rename;
+    other.app.input_buffer = target.to_string_lossy().to_string();
+    other.key(KeyCode::Enter);
+    let confirm_mode = other.mode;
+    other.key(KeyCode::Char('n'));
+    let kept_text = std::fs::read_to_string(&target);
+    let _ = std::fs::remove_file(&stray);
+    let _ = std::fs::remove_file(&target);
+
+    assert_eq!(stray_text.unwrap(), "unrelated\n");
+    assert_eq!(target_text.unwrap(), "draft\n");
+    assert_eq!(s.app.current_buffer().file_path.as_ref(), Some(&target));
+    assert_eq!(confirm_mode, Mode::Confirm(ConfirmType::Overwrite));
+    assert_eq!(kept_text.unwrap(), "draft\n");
+    assert!(other.app.current_buffer().modified);
+}
+
+#[test]
+fn go_to_line_accepts_relative_and_clamps() {
+    let mut s = Session::new();
+    s.app.current_buffer_mut().lines = (1..=20).map(|i| format!("line {}", i)).collect();
+    fn go(s: &mut Session, input: &str) -> usize {
+        s.ctrl('g');
+        s.type_str(input);
+        s.key(KeyCode::Enter);
+        s.cursor().1
+    }
+
+    assert_eq!(go(&mut s, "10"), 9);
+    assert_eq!(go(&mut s, "+5"), 14);
+    assert_eq!(go(&mut s, "-3"), 11);
+    assert_eq!(go(&mut s, " 2 "), 1);
+    assert_eq!(go(&mut s, "-50"), 0);
+    assert_eq!(go(&mut s, "500"), 19);
+    assert_eq!(go(&mut s, "+500"), 19);
+    assert_eq!(go(&mut s, "0"), 0);
+
+    assert_eq!(go(&mut s, "7"), 6);
+    for garbage in ["abc", "", "+", "-x", "3.5", "1e3"] {
+        assert_eq!(go(&mut s, garbage), 6, "{:?}", garbage);
+    }
+}
+
+#[test]
+fn chord_prefix_expires_after_the_timeout() {
+    let combos = vec![
+        KeyCombo {
+            code: KeyCode::Char('k'),
+            modifiers: KeyModifiers::CONTROL,
+        },
+        KeyCombo {
+            code: KeyCode::Char('s'),
+            modifiers: KeyModifiers::CONTROL,
+        },
+    ];
+    let mut state = KeybindState::default();
+    state
+        .custom_binds
+        .insert(combos.clone(), KeybindAction::Save);
+    let timeout = Duration::from_millis(1000);
+    let start = Instant::now();
+
+    assert_eq!(
+        state.resolve(combos[0], start, timeout),
+        ChordOutcome::Pending
+    );
+    let in_time = start + Duration::from_millis(900);
+    assert_eq!(
+        state.resolve(combos[1], in_time, timeout),
+        ChordOutcome::Matched(KeybindAction::Save)
+    );
+
+    assert_eq!(
+        state.resolve(combos[0], start, timeout),
+        ChordOutcome::Pending
+    );
+    let too_late = start + Duration::from_millis(1100);
+    assert_eq!(
+        state.resolve(combos[1], too_late, timeout),
+        ChordOutcome::Unbound
+    );
+    assert!(state.pending_chord.is_empty());
+}
+
+#[test]
+fn close_confirm_opens_on_the_configured_choice() {
+    let mut s = Session::new();
+    s.config.confirm_close_default = ConfirmChoice::Cancel;
+    s.ctrl('n');
+    s.type_str("draft");
+    s.ctrl('w');
+    assert_eq!(s.mode, Mode::Confirm(ConfirmType::CloseTab));
+    assert_eq!(s.app.confirm_choice, ConfirmChoice::Cancel);
+    s.key(KeyCode::Enter);
+    assert_eq!(s.app.buffers.len(), 2);
+
+    s.config.confirm_close_default = ConfirmChoice::No;
+    s.key(KeyCode::Esc);
+    s.active_tab = MenuTab::File;
+    s.dropdown_idx = 2;
+    s.key(KeyCode::Enter);
+    assert_eq!(s.mode, Mode::Confirm(ConfirmType::CloseTab));
+    assert_eq!(s.app.confirm_choice, ConfirmChoice::No);
+    s.key(KeyCode::Enter);
+    assert_eq!(s.app.buffers.len(), 1);
+    assert_eq!(s.mode, Mode::Editing);
+}
+
+#[test]
+fn exit_menu_entry_asks_the_loop_to_stop() {
+    let mut s = Session::new();
+    s.key(KeyCode::Esc);
+    s.active_tab = MenuTab::Re;
+    s.dropdown_idx = 1;
+    assert!(!s.press(KeyCode::Enter, KeyModifiers::NONE));
+    assert_eq!(s.mode, Mode::Help);
+
+    s.key(KeyCode::Esc);
+    s.key(KeyCode::Esc);
+    s.active_tab = MenuTab::Re;
+    s.dropdown_idx = 2;
+    assert!(s.press(KeyCode::Enter, KeyModifiers::NONE));
+}
+
+#[test]
+fn ctrl_c_copies_instead_of_quitting() {
+    let mut s = Session::new();
+    s.type_str("hello");
+    s.ctrl('a');
+    assert!(!s.press(KeyCode::Char('c'), KeyModifiers::CONTROL));
+    assert_eq!(s.app.clipboard, "hello");
+    assert_eq!(s.app.status_flash.as_deref(), Some("COPIED"));
+    assert_eq!(s.mode, Mode::Editing);
+    assert_eq!(s.lines(), ["hello"]);
+    assert_eq!(
+        KeybindAction::default_for(KeyCombo {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+        }),
+        Some(KeybindAction::Copy)
+    );
+}
+
+fn color_editor_session() -> Session {
+    let mut s = Session::new();
+    s.app.populate_color_entries();
+    s.mode = Mode::ColorEditor;
+    s
+}
+
+#[test]
+fn color_editor_esc_cancels_the_field_before_leaving() {
+    let mut s = color_editor_session();
+    let original = s.app.color_entries[0].current_hex.clone();
+    s.key(KeyCode::Enter);
+    for _ in 0..7 {
+        s.key(KeyCode::Backspace);
+    }
+    s.type_str("123abc");
+    assert_eq!(s.app.color_entries[0].current_hex, "#123ABC");
+
+    s.key(KeyCode::Esc);
+    assert!(!s.app.editing_hex);
+    assert_eq!(s.mode, Mode::ColorEditor);
+    assert_eq!(s.app.color_entries[0].current_hex, original);
+
+    s.key(KeyCode::Esc);
+    assert_eq!(s.mode, Mode::Settings);
+}
+
+#[test]
+fn color_editor_resets_only_the_selected_entry() {
+    let mut s = color_editor_session();
+    for entry in &mut s.app.color_entries[..2] {
+        entry.current_hex = "#010203".to_string();
+    }
+    s.key(KeyCode::Down);
+    s.key(KeyCode::Char('r'));
+
+    let defaults = PaletteConfig::default();
+    let entries = &s.app.color_entries;
+    assert_eq!(entries[0].current_hex, "#010203");
+    assert_eq!(
+        Some(entries[1].current_hex.as_str()),
+        defaults.get(&entries[1].name)
+    );
+    let flash = format!("{} RESET", entries[1].name.to_uppercase());
+    assert_eq!(s.app.status_flash, Some(flash));
+
+    s.key(KeyCode::Enter);
+    s.key(KeyCode::Backspace);
+    s.key(KeyCode::Char('r'));
+    let edited = &s.app.color_entries[1].current_hex;
+    assert_eq!(edited.len(), 6);
+    assert!(s.app.editing_hex);
+}
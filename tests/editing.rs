@@ -0,0 +1,626 @@
+use fero::editor;
+use fero::state::{
+    AppState, Buffer, BufferInfo, Config, IndentStyle, PreviewSkip, Selection, WipeConfirm,
+    MIN_UNDO_LIMIT,
+};
+
+#[test]
+fn edit_and_undo_through_app_state() {
+    let mut app = AppState::new();
+
+    app.push_undo();
+    let buf = app.current_buffer_mut();
+    buf.lines[0].push_str("hello");
+    buf.cursor_x = 5;
+    buf.modified = true;
+
+    app.push_undo();
+    let buf = app.current_buffer_mut();
+    buf.lines.push("world".to_string());
+    buf.cursor_y = 1;
+
+    assert_eq!(app.current_buffer().lines, ["hello", "world"]);
+
+    assert!(app.undo());
+    assert_eq!(app.current_buffer().lines, ["hello"]);
+    assert_eq!(app.current_buffer().cursor_x, 5);
+
+    assert!(app.undo());
+    assert_eq!(app.current_buffer().lines, [""]);
+    assert!(!app.undo());
+
+    assert!(app.redo());
+    assert!(app.redo());
+    assert_eq!(app.current_buffer().lines, ["hello", "world"]);
+}
+
+#[test]
+fn saved_buffer_loads_back() {
+    let path = std::env::temp_dir().join(format!("fero-lib-{}.txt", std::process::id()));
+    let path_str = path.to_str().unwrap();
+
+    let mut app = AppState::new();
+    app.current_buffer_mut().lines = vec!["fn main() {".to_string(), "}".to_string()];
+    editor::save_to_file(&app.current_buffer().lines, path_str).unwrap();
+
+    let lines = editor::load_from_file(path_str).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(lines, app.current_buffer().lines);
+}
+
+#[test]
+fn explorer_starts_in_the_buffer_directory() {
+    let mut app = AppState::new();
+    assert_eq!(app.explorer_start_dir(), std::env::current_dir().unwrap());
+
+    let dir = std::env::temp_dir().join("fero-project");
+    app.current_buffer_mut().file_path = Some(dir.join("main.rs"));
+    assert_eq!(app.explorer_start_dir(), dir);
+
+    app.current_buffer_mut().file_path = Some("notes.txt".into());
+    assert_eq!(app.explorer_start_dir(), std::env::current_dir().unwrap());
+}
+
+#[test]
+fn rename_moves_the_file_on_disk() {
+    let dir = std::env::temp_dir();
+    let old_path = dir.join(format!("fero-rename-old-{}.txt", std::process::id()));
+    let new_path = dir.join(format!("fero-rename-new-{}.txt", std::process::id()));
+    std::fs::write(&old_path, "hello\n").unwrap();
+
+    let mut app = AppState::new();
+    app.current_buffer_mut().file_path = Some(old_path.clone());
+    let renamed = app.rename_current_file(new_path.clone());
+
+    let old_exists = old_path.exists();
+    let new_exists = new_path.exists();
+    let _ = std::fs::remove_file(&new_path);
+    renamed.unwrap();
+    assert!(!old_exists);
+    assert!(new_exists);
+    assert_eq!(app.current_buffer().file_path.as_ref(), Some(&new_path));
+    assert_eq!(
+        app.current_buffer().filename,
+        new_path.file_name().unwrap().to_string_lossy()
+    );
+}
+
+#[test]
+fn final_newlines_collapse_to_one() {
+    let path = std::env::temp_dir().join(format!("fero-newline-{}.txt", std::process::id()));
+    let path_str = path.to_str().unwrap();
+
+    for content in ["a\nb", "a\nb\n", "a\nb\n\n\n"] {
+        std::fs::write(&path, content).unwrap();
+        let mut lines = editor::load_from_file(path_str).unwrap();
+        editor::trim_final_newlines(&mut lines);
+        assert_eq!(lines, ["a", "b"]);
+
+        editor::save_to_file(&lines, path_str).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb\n");
+    }
+
+    let mut blank = vec![String::new(), String::new()];
+    editor::trim_final_newlines(&mut blank);
+    assert_eq!(blank, [""]);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn wipe_confirmation_scales_with_the_buffer() {
+    let mut buf = Buffer::new("notes.txt".to_string());
+    assert_eq!(buf.wipe_confirm(100), WipeConfirm::Quick);
+
+    buf.modified = true;
+    assert_eq!(buf.wipe_confirm(100), WipeConfirm::Quick);
+
+    buf.lines = vec!["draft".to_string()];
+    assert_eq!(buf.wipe_confirm(100), WipeConfirm::Typed);
+
+    buf.modified = false;
+    assert_eq!(buf.wipe_confirm(100), WipeConfirm::Quick);
+
+    buf.lines = vec!["line".to_string(); 101];
+    assert_eq!(buf.wipe_confirm(100), WipeConfirm::Typed);
+    assert_eq!(buf.wipe_confirm(200), WipeConfirm::Quick);
+}
+
+#[test]
+fn pasted_block_takes_the_current_indent() {
+    let block = "        if ready {\n            go();\n\n        }";
+    assert_eq!(
+        editor::reindent_block(block, "    "),
+        "if ready {\n        go();\n\n    }"
+    );
+
+    let ragged = "  a\n    b\n   c";
+    assert_eq!(editor::reindent_block(ragged, "\t"), "a\n\t  b\n\t c");
+    assert_eq!(editor::reindent_block("single", "    "), "single");
+}
+
+#[test]
+fn buffer_syntax_override_beats_global_setting() {
+    let mut buf = Buffer::new("main.rs".to_string());
+    assert!(buf.syntax_enabled(true));
+    assert!(!buf.syntax_enabled(false));
+
+    buf.syntax_override = Some(false);
+    assert!(!buf.syntax_enabled(true));
+
+    buf.syntax_override = Some(true);
+    assert!(buf.syntax_enabled(false));
+}
+
+#[test]
+fn explorer_preview_skips_binary_and_large_files() {
+    assert_eq!(editor::preview_skip(120, b"fn main() {}\n"), None);
+    assert_eq!(
+        editor::preview_skip(120, b"\x7fELF\x00\x01"),
+        Some(PreviewSkip::Binary)
+    );
+    assert_eq!(
+        editor::preview_skip(editor::PREVIEW_MAX_BYTES + 1, b"text"),
+        Some(PreviewSkip::Large)
+    );
+}
+
+#[test]
+fn explorer_preview_keeps_the_first_lines() {
+    let content = "one\n\ttwo\nthree\nfour\n";
+    assert_eq!(editor::truncate_preview(content, 2), ["one", "    two"]);
+    assert_eq!(editor::truncate_preview(content, 10).len(), 4);
+    assert!(editor::truncate_preview("", 5).is_empty());
+}
+
+#[test]
+fn empty_state_hint_only_for_a_fresh_buffer() {
+    let mut app = AppState::new();
+    assert!(app.show_empty_hint());
+
+    app.current_buffer_mut().lines[0].push('x');
+    assert!(!app.show_empty_hint());
+    app.current_buffer_mut().lines[0].clear();
+    assert!(app.show_empty_hint());
+
+    app.current_buffer_mut().file_path = Some("notes.txt".into());
+    assert!(!app.show_empty_hint());
+    app.current_buffer_mut().file_path = None;
+
+    app.hint_dismissed = true;
+    assert!(!app.show_empty_hint());
+}
+
+#[test]
+fn align_indent_evens_out_a_ragged_block() {
+    let mut lines: Vec<String> = ["  let a = 1;", "      let b = 2;", "", "\t let c = 3;"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(editor::align_indent(&mut lines, Some("    ")), 3);
+    assert_eq!(
+        lines,
+        ["    let a = 1;", "    let b = 2;", "", "    let c = 3;"]
+    );
+    assert_eq!(editor::align_indent(&mut lines, Some("    ")), 0);
+}
+
+#[test]
+fn align_indent_defaults_to_the_first_line() {
+    let mut lines: Vec<String> = ["\tif ready {", "go();", "        }"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(editor::align_indent(&mut lines, None), 2);
+    assert_eq!(lines, ["\tif ready {", "\tgo();", "\t}"]);
+}
+
+#[test]
+fn word_motion_respects_extra_word_chars() {
+    let line = "  foo-bar-baz qux";
+    assert_eq!(editor::word_right(line, 0, ""), 5);
+    assert_eq!(editor::word_right(line, 5, ""), 9);
+    assert_eq!(editor::word_left(line, 13, ""), 10);
+    assert_eq!(editor::word_left(line, 10, ""), 6);
+
+    assert_eq!(editor::word_right(line, 0, "-"), 13);
+    assert_eq!(editor::word_right(line, 13, "-"), 17);
+    assert_eq!(editor::word_left(line, 13, "-"), 2);
+    assert_eq!(editor::word_left(line, 2, "-"), 0);
+}
+
+#[test]
+fn buffer_info_summarizes_the_buffer() {
+    let config = Config::default();
+    let mut buf = Buffer::new("main.rs".to_string());
+    buf.lines = vec![
+        "fn main() {".to_string(),
+        "\tlet é = 1;".to_string(),
+        "}".to_string(),
+    ];
+    buf.cursor_y = 1;
+    buf.cursor_x = 1;
+    buf.modified = true;
+
+    let info = BufferInfo::new(&buf, None, &config);
+    assert_eq!((info.lines, info.words, info.chars), (3, 8, 25));
+    assert_eq!(info.selected_chars, None);
+    assert_eq!((info.cursor_line, info.cursor_column), (2, 5));
+    assert_eq!(info.path, "(not saved)");
+    assert!(info.modified);
+    assert_eq!(info.language, "rs");
+    assert_eq!(info.line_ending, "LF");
+
+    let selection = Selection {
+        start_x: 3,
+        start_y: 0,
+        end_x: 7,
+        end_y: 1,
+    };
+    let info = BufferInfo::new(&buf, Some(&selection), &config);
+    assert_eq!(info.selected_chars, Some(15));
+}
+
+#[test]
+fn undo_history_belongs_to_each_buffer() {
+    let mut app = AppState::new();
+    app.push_undo();
+    app.current_buffer_mut().lines[0].push_str("buffer a");
+
+    app.add_buffer(Buffer::new("b.txt".to_string()));
+    assert!(!app.undo());
+    app.push_undo();
+    app.current_buffer_mut().lines[0].push_str("buffer b");
+    assert!(app.undo());
+    assert_eq!(app.current_buffer().lines, [""]);
+    assert!(!app.undo());
+
+    app.switch_buffer(0);
+    assert_eq!(app.current_buffer().lines, ["buffer a"]);
+    assert!(app.undo());
+    assert_eq!(app.current_buffer().lines, [""]);
+    assert_eq!(app.buffers[1].redo_stack.len(), 1);
+}
+
+#[test]
+fn markdown_headings_need_a_space_after_the_hashes() {
+    assert!(editor::is_markdown_heading("# Title"));
+    assert!(editor::is_markdown_heading("   ### Section"));
+    assert!(editor::is_markdown_heading("##"));
+    assert!(!editor::is_markdown_heading("#hashtag"));
+    assert!(!editor::is_markdown_heading("####### too deep"));
+    assert!(!editor::is_markdown_heading("    # indented code"));
+}
+
+#[test]
+fn markdown_fence_state_carries_across_lines() {
+    use editor::MarkdownLine::{Code, Heading, Text};
+    let lines: Vec<String> = ["# Intro", "```rust", "# not a heading", "```", "`x` here"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(
+        editor::markdown_lines(&lines, 0..5),
+        [Heading, Code, Code, Code, Text]
+    );
+    assert_eq!(editor::markdown_lines(&lines, 2..4), [Code, Code]);
+    assert_eq!(editor::markdown_lines(&lines, 4..10), [Text]);
+    assert_eq!(editor::inline_code_spans("`x` and `y"), [(0, 3)]);
+}
+
+fn owned(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn indent_detection_reads_tabs_and_space_widths() {
+    let tabs = owned(&[
+        "fn main() {",
+        "\tlet x = 1;",
+        "\tif x {",
+        "\t\tx;",
+        "\t}",
+        "}",
+    ]);
+    assert_eq!(editor::detect_indent(&tabs), Some(IndentStyle::Tabs));
+
+    let two = owned(&["a:", "  b:", "    c: 1", "  d: 2"]);
+    assert_eq!(editor::detect_indent(&two), Some(IndentStyle::Spaces(2)));
+
+    let four = owned(&["def f():", "    if x:", "        return 1", "    return 2"]);
+    assert_eq!(editor::detect_indent(&four), Some(IndentStyle::Spaces(4)));
+
+    assert_eq!(editor::detect_indent(&owned(&["flat", "", "text"])), None);
+}
+
+#[test]
+fn indent_detection_follows_the_majority_when_mixed() {
+    let mostly_tabs = owned(&["{", "\ta", "\tb", "    c", "}"]);
+    assert_eq!(editor::detect_indent(&mostly_tabs), Some(IndentStyle::Tabs));
+
+    let mostly_spaces = owned(&["{", "    a", "    b", "\tc", "}"]);
+    assert_eq!(
+        editor::detect_indent(&mostly_spaces),
+        Some(IndentStyle::Spaces(4))
+    );
+}
+
+#[test]
+fn tabs_and_spaces_round_trip_with_alignment() {
+    let original = owned(&["fn f() {", "\tcall(a,", "\t      b);", "\t\tx", "}"]);
+
+    let mut lines = original.clone();
+    assert_eq!(editor::tabs_to_spaces(&mut lines, 4), 3);
+    assert_eq!(
+        lines,
+        ["fn f() {", "    call(a,", "          b);", "        x", "}"]
+    );
+
+    assert_eq!(editor::spaces_to_tabs(&mut lines, 4), 3);
+    assert_eq!(lines, ["fn f() {", "\tcall(a,", "\t\t  b);", "\t\tx", "}"]);
+
+    editor::tabs_to_spaces(&mut lines, 4);
+    editor::spaces_to_tabs(&mut lines, 4);
+    assert_eq!(lines[2], "\t\t  b);");
+}
+
+#[test]
+fn io_errors_map_to_short_messages() {
+    use std::io::{Error, ErrorKind};
+
+    let cases = [
+        (ErrorKind::PermissionDenied, "PERMISSION DENIED"),
+        (ErrorKind::NotFound, "DIRECTORY MISSING"),
+        (ErrorKind::StorageFull, "DISK FULL"),
+        (ErrorKind::IsADirectory, "IS A DIRECTORY"),
+    ];
+    for (kind, message) in cases {
+        assert_eq!(editor::describe_io_error(&Error::from(kind)), message);
+    }
+
+    let other = Error::other("pipe closed");
+    assert_eq!(editor::describe_io_error(&other), "pipe closed");
+}
+
+#[cfg(unix)]
+#[test]
+fn saving_through_a_symlink_keeps_the_link() {
+    use std::os::unix::fs::symlink;
+
+    let dir = std::env::temp_dir().join(format!("fero-symlink-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let real = dir.join("real.txt");
+    let link = dir.join("link.txt");
+    std::fs::write(&real, "old\n").unwrap();
+    symlink(&real, &link).unwrap();
+
+    let lines = vec!["new".to_string()];
+    editor::save_to_file(&lines, link.to_str().unwrap()).unwrap();
+    assert!(editor::is_symlink(&link));
+    assert_eq!(std::fs::read_to_string(&real).unwrap(), "new\n");
+
+    let dangling = dir.join("dangling.txt");
+    symlink(dir.join("gone.txt"), &dangling).unwrap();
+    let err = editor::save_to_file(&lines, dangling.to_str().unwrap()).unwrap_err();
+    assert_eq!(editor::describe_io_error(&err), "BROKEN SYMLINK");
+    assert!(!dir.join("gone.txt").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn undo_records_only_the_changed_middle_lines() {
+    let limit = 100;
+    let mut buf = Buffer::new("diff.txt".to_string());
+    buf.lines = owned(&["a", "b", "c", "d", "e"]);
+    buf.push_undo(limit);
+
+    buf.lines.insert(2, "new".to_string());
+    buf.push_undo(limit);
+    let insert = buf.undo_stack.last().unwrap();
+    assert_eq!((insert.start, insert.new_len), (2, 1));
+    assert!(insert.old_lines.is_empty());
+
+    buf.lines.remove(3);
+    buf.push_undo(limit);
+    let delete = buf.undo_stack.last().unwrap();
+    assert_eq!((delete.start, delete.new_len), (3, 0));
+    assert_eq!(delete.old_lines, ["c"]);
+
+    buf.lines[1] = "B".to_string();
+    buf.push_undo(limit);
+    let replace = buf.undo_stack.last().unwrap();
+    assert_eq!((replace.start, replace.new_len), (1, 1));
+    assert_eq!(replace.old_lines, ["b"]);
+    assert_eq!(buf.lines, ["a", "B", "new", "d", "e"]);
+
+    assert!(buf.undo(limit));
+    assert_eq!(buf.lines, ["a", "b", "new", "d", "e"]);
+    assert!(buf.undo(limit));
+    assert_eq!(buf.lines, ["a", "b", "new", "c", "d", "e"]);
+    assert!(buf.undo(limit));
+    assert_eq!(buf.lines, ["a", "b", "c", "d", "e"]);
+
+    assert!(buf.redo(limit));
+    assert!(buf.redo(limit));
+    assert!(buf.redo(limit));
+    assert_eq!(buf.lines, ["a", "B", "new", "d", "e"]);
+}
+
+#[test]
+fn closing_a_tab_returns_to_the_most_recent_one() {
+    let mut app = AppState::new();
+    for name in ["b.txt", "c.txt", "d.txt"] {
+        app.add_buffer(Buffer::new(name.to_string()));
+    }
+    app.switch_buffer(1);
+    app.switch_buffer(3);
+
+    app.close_buffer(3);
+    assert_eq!(app.current_buffer().filename, "b.txt");
+
+    app.close_buffer(0);
+    assert_eq!(app.current_buffer().filename, "b.txt");
+    assert_eq!(app.active_buffer, 0);
+
+    app.close_buffer(0);
+    assert_eq!(app.current_buffer().filename, "c.txt");
+}
+
+fn symbol_names(lines: &[&str], ext: &str) -> Vec<(usize, &'static str, String)> {
+    editor::extract_symbols(&owned(lines), ext)
+        .into_iter()
+        .map(|symbol| (symbol.line, symbol.kind, symbol.name))
+        .collect()
+}
+
+#[test]
+fn rust_symbols_skip_modifiers_and_nested_items() {
+    let source = [
+        "use std::fmt;",
+        "pub(crate) struct Point<T> {",
+        "    fn not_top_level() {}",
+        "}",
+        "impl<T> Display for Point<T> {",
+        "pub async fn load(path: &str) {}",
+        "enum Mode { A }",
+        "macro_rules! twice {",
+    ];
+    assert_eq!(
+        symbol_names(&source, "rs"),
+        [
+            (1, "struct", "Point".to_string()),
+            (4, "impl", "Display for Point<T>".to_string()),
+            (5, "fn", "load".to_string()),
+            (6, "enum", "Mode".to_string()),
+            (7, "macro_rules!", "twice".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn python_symbols_are_top_level_defs_and_classes() {
+    let source = [
+        "import os",
+        "class Parser(Base):",
+        "    def method(self):",
+        "def main():",
+        "async def fetch():",
+    ];
+    assert_eq!(
+        symbol_names(&source, "py"),
+        [
+            (1, "class", "Parser".to_string()),
+            (3, "def", "main".to_string()),
+            (4, "def", "fetch".to_string()),
+        ]
+    );
+    assert!(symbol_names(&source, "txt").is_empty());
+}
+
+#[test]
+fn horizontal_scroll_clamps_to_the_longest_visible_line() {
+    let mut buf = Buffer::new("wide.txt".to_string());
+    buf.lines = vec![
+        "a".repeat(30),
+        "b".repeat(50),
+        "\tc".to_string(),
+        "d".repeat(200),
+    ];
+
+    buf.scroll_horizontal(100, 20, 3, 4);
+    assert_eq!(buf.viewport_offset_x, 30);
+    buf.scroll_horizontal(-4, 20, 3, 4);
+    assert_eq!(buf.viewport_offset_x, 26);
+    buf.scroll_horizontal(-100, 20, 3, 4);
+    assert_eq!(buf.viewport_offset_x, 0);
+
+    buf.viewport_offset_y = 2;
+    buf.scroll_horizontal(4, 20, 1, 4);
+    assert_eq!(buf.viewport_offset_x, 0);
+    buf.scroll_horizontal(500, 20, 2, 4);
+    assert_eq!(buf.viewport_offset_x, 180);
+}
+
+#[test]
+fn repeated_push_undo_without_changes_records_one_entry() {
+    let mut buf = Buffer::new("undo.txt".to_string());
+    buf.push_undo(100);
+    buf.push_undo(100);
+    assert_eq!(buf.undo_depth(), 1);
+    assert!(buf.undo_stack.is_empty());
+
+    buf.lines[0].push('x');
+    buf.push_undo(100);
+    buf.push_undo(100);
+    assert_eq!(buf.undo_stack.len(), 1);
+    assert_eq!(buf.undo_depth(), 2);
+}
+
+#[test]
+fn undo_limit_caps_the_history() {
+    let mut app = AppState::new();
+    app.undo_limit = 12;
+    for _ in 0..30 {
+        app.push_undo();
+        app.current_buffer_mut().lines[0].push('x');
+    }
+    app.push_undo();
+    assert_eq!(app.current_buffer().undo_stack.len(), 12);
+
+    let mut undone = 0;
+    while app.undo() {
+        undone += 1;
+    }
+    assert_eq!(undone, 12);
+    assert_eq!(app.current_buffer().lines[0], "x".repeat(18));
+
+    let mut buf = Buffer::new("tiny.txt".to_string());
+    for _ in 0..30 {
+        buf.push_undo(1);
+        buf.lines[0].push('y');
+    }
+    buf.push_undo(1);
+    assert_eq!(buf.undo_stack.len(), MIN_UNDO_LIMIT);
+}
+
+#[test]
+fn new_tabs_follow_the_name_pattern_and_template() {
+    let config = Config {
+        new_tab_name: "scratch-{n}.md".to_string(),
+        new_tab_template: "# Title\n\nbody".to_string(),
+        ..Config::default()
+    };
+    let mut app = AppState::new();
+    app.open_new_buffer(&config);
+    app.open_new_buffer(&config);
+
+    assert_eq!(app.buffers[1].filename, "scratch-1.md");
+    assert_eq!(app.current_buffer().filename, "scratch-2.md");
+    assert_eq!(app.current_buffer().lines, ["# Title", "", "body"]);
+    assert!(!app.current_buffer().modified);
+}
+
+#[test]
+fn new_tabs_get_distinct_names_when_the_pattern_collides() {
+    let config = Config {
+        new_tab_name: "notes.txt".to_string(),
+        ..Config::default()
+    };
+    let mut app = AppState::new();
+    app.buffers[0].filename = "notes-2.txt".to_string();
+    for _ in 0..3 {
+        app.open_new_buffer(&config);
+    }
+
+    let names: Vec<&str> = app.buffers.iter().map(|b| b.filename.as_str()).collect();
+    assert_eq!(
+        names,
+        ["notes-2.txt", "notes.txt", "notes-3.txt", "notes-4.txt"]
+    );
+
+    app.buffers[1].filename = "README".to_string();
+    app.buffers[2].filename = ".env".to_string();
+    assert_eq!(app.unique_buffer_name("README"), "README-2");
+    assert_eq!(app.unique_buffer_name(".env"), ".env-2");
+}
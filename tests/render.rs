@@ -0,0 +1,312 @@
+use chrono::{Duration, Local};
+use fero::editor;
+use fero::state::{
+    help_visible_rows, Buffer, Config, Selection, COLOR_EDITOR_ROWS, DIR_MARKER, FILE_MARKER,
+    HELP_BINDINGS, HELP_CHROME_ROWS, HELP_ROWS, TAB_NAME_MAX,
+};
+use fero::ui::{
+    clipped_right, color_editor_window, explorer_label, explorer_width, first_visible_tab,
+    format_line_number, format_save_age, keywords_for, line_keywords, middle_ellipsis,
+    overflow_span, restore_terminal_with, ruler_screen_x, selection_columns, tab_label,
+    word_occurrences, ShutdownStep,
+};
+use std::sync::atomic::AtomicBool;
+
+#[test]
+fn ruler_column_follows_horizontal_scroll() {
+    assert_eq!(ruler_screen_x(80, 0, 100), Some(80));
+    assert_eq!(ruler_screen_x(80, 30, 100), Some(50));
+    assert_eq!(ruler_screen_x(80, 80, 100), Some(0));
+    assert_eq!(ruler_screen_x(80, 81, 100), None);
+    assert_eq!(ruler_screen_x(80, 0, 60), None);
+}
+
+#[test]
+fn overflow_span_starts_at_the_limit() {
+    assert_eq!(overflow_span(0, 120, 80), Some((80, 120)));
+    assert_eq!(overflow_span(90, 120, 80), Some((90, 120)));
+    assert_eq!(overflow_span(0, 80, 80), None);
+    assert_eq!(overflow_span(0, 40, 80), None);
+}
+
+#[test]
+fn gutter_fits_the_largest_line_number() {
+    let mut buf = Buffer::new("big.txt".to_string());
+    assert_eq!(buf.gutter_width(false), 0);
+    assert_eq!(buf.gutter_width(true), 3);
+    assert_eq!(format_line_number(1, 3), " 1 ");
+
+    for (count, width) in [(9, 3), (10, 4), (999_999, 8), (1_000_000, 9)] {
+        buf.lines = vec![String::new(); count];
+        let gutter = buf.gutter_width(true);
+        assert_eq!(gutter, width);
+        assert_eq!(format_line_number(count, gutter).len(), gutter as usize);
+        assert_eq!(format_line_number(1, gutter).len(), gutter as usize);
+    }
+
+    assert_eq!(format_line_number(42, 0), " 42 ");
+}
+
+#[test]
+fn explorer_width_fits_the_terminal() {
+    assert_eq!(explorer_width(40, 120), 40);
+    assert_eq!(explorer_width(60, 30), 30);
+    assert_eq!(explorer_width(4, 120), 16);
+    assert_eq!(explorer_width(40, 10), 10);
+}
+
+#[test]
+fn long_tab_names_lose_their_middle() {
+    assert_eq!(middle_ellipsis("main.rs", 12), "main.rs");
+    assert_eq!(middle_ellipsis("verylongname.rs", 13), "verylo…ame.rs");
+    assert_eq!(middle_ellipsis("verylongname.rs", 13).chars().count(), 13);
+    assert_eq!(middle_ellipsis("abcdef", 1), "…");
+    assert_eq!(middle_ellipsis("abcdef", 0), "");
+}
+
+#[test]
+fn tab_bar_scrolls_to_the_active_tab() {
+    let widths = [10, 10, 10, 10, 10];
+    assert_eq!(first_visible_tab(&widths, 0, 35), 0);
+    assert_eq!(first_visible_tab(&widths, 2, 35), 0);
+    assert_eq!(first_visible_tab(&widths, 3, 35), 1);
+    assert_eq!(first_visible_tab(&widths, 4, 35), 2);
+    assert_eq!(first_visible_tab(&widths, 4, 5), 4);
+    assert_eq!(first_visible_tab(&[], 0, 35), 0);
+}
+
+#[test]
+fn aliased_extensions_use_builtin_keywords() {
+    let mut config = Config::default();
+    let keywords = keywords_for(config.language_for("stubs.pyi")).unwrap();
+    assert!(keywords.contains("def"));
+    assert!(keywords_for(config.language_for("notes.txt")).is_none());
+
+    config
+        .extension_aliases
+        .insert("txt".to_string(), "rs".to_string());
+    assert!(keywords_for(config.language_for("notes.txt"))
+        .unwrap()
+        .contains("fn"));
+}
+
+#[test]
+fn lines_past_the_right_edge_are_flagged() {
+    assert!(clipped_right(81, 0, 80));
+    assert!(!clipped_right(80, 0, 80));
+    assert!(!clipped_right(20, 0, 80));
+    assert!(clipped_right(150, 50, 80));
+    assert!(!clipped_right(130, 50, 80));
+}
+
+#[test]
+fn tab_label_uses_the_configured_glyphs() {
+    let mut config = Config::default();
+    let mut buf = Buffer::new("main.rs".to_string());
+    assert_eq!(tab_label(&buf, &config), "  main.rs");
+
+    buf.modified = true;
+    assert_eq!(tab_label(&buf, &config), "● main.rs");
+
+    config.modified_glyph = "*".to_string();
+    assert_eq!(tab_label(&buf, &config), "* main.rs");
+}
+
+#[test]
+fn ascii_mode_keeps_explorer_labels_ascii() {
+    let mut config = Config::default();
+    let entries = [
+        format!("{}src", DIR_MARKER),
+        format!("{}main.rs", FILE_MARKER),
+    ];
+    assert!(!explorer_label(&entries[0], &config).is_ascii());
+
+    config.ascii_mode = true;
+    for entry in &entries {
+        assert!(explorer_label(entry, &config).is_ascii());
+    }
+    assert_eq!(explorer_label(&entries[0], &config), "+ src");
+
+    let mut buf = Buffer::new("a-rather-long-file-name-for-a-tab.rs".to_string());
+    buf.modified = true;
+    assert!(tab_label(&buf, &config).is_ascii());
+    assert_eq!(tab_label(&buf, &config).chars().count(), 2 + TAB_NAME_MAX);
+}
+
+#[test]
+fn ascii_mode_leaves_file_names_alone() {
+    let config = Config {
+        ascii_mode: true,
+        ..Config::default()
+    };
+    let buf = Buffer::new("notes—draft•1.md".to_string());
+    assert_eq!(tab_label(&buf, &config), "  notes—draft•1.md");
+
+    let entry = format!("{}a→b…c.txt", FILE_MARKER);
+    assert_eq!(explorer_label(&entry, &config), "- a→b…c.txt");
+    assert_eq!(explorer_label("plain", &config), "plain");
+}
+
+#[test]
+fn wide_characters_take_two_columns() {
+    let line = "ab日本c";
+    assert_eq!(editor::str_width(line), 7);
+    assert_eq!(editor::display_column(line, "ab日".len(), 4), 4);
+    assert_eq!(editor::display_column("\t日", "\t日".len(), 4), 6);
+    assert_eq!(editor::expand_tabs("日\tx", 4), "日  x");
+
+    assert_eq!(editor::column_to_byte(line, 2), 2);
+    assert_eq!(editor::column_to_byte(line, 3), 2);
+    assert_eq!(editor::column_to_byte(line, 4), "ab日".len());
+    assert_eq!(editor::column_to_byte(line, 9), line.len());
+}
+
+#[test]
+fn long_lines_only_scan_the_visible_window() {
+    let line = "x\t".repeat(2_500_000);
+    assert_eq!(editor::byte_at_display_column(&line, 240, 4), 120);
+    let visible = editor::expand_tabs_until(&line, 4, 240);
+    assert_eq!(visible.len(), 240);
+    assert_eq!(editor::display_width_capped(&line, 4, 241), 241);
+
+    let words = vec!["ab ".repeat(1_000_000)];
+    let matches = word_occurrences(&words, 0..1, "ab", 30, 4, "");
+    assert_eq!(matches.len(), 11);
+    assert_eq!(matches.last(), Some(&(0, 30, 32)));
+
+    assert_eq!(editor::expand_tabs_until("a\tb", 4, 2), "a   ");
+    assert_eq!(editor::display_width_capped("short", 4, 80), 5);
+}
+
+#[test]
+fn disabled_languages_render_without_keywords() {
+    let config = Config {
+        language_syntax: [("py".to_string(), false)].into_iter().collect(),
+        ..Config::default()
+    };
+    let mut script = Buffer::new("tool.pyi".to_string());
+    let rust = Buffer::new("main.rs".to_string());
+    assert!(line_keywords(&script, &config).is_none());
+    assert!(line_keywords(&rust, &config).is_some_and(|k| k.contains("fn")));
+
+    script.syntax_override = Some(true);
+    assert!(line_keywords(&script, &config).is_some());
+
+    let off = Config {
+        syntax_highlight: false,
+        ..Config::default()
+    };
+    assert!(line_keywords(&rust, &off).is_none());
+}
+
+#[test]
+fn selection_columns_expand_leading_tabs() {
+    let line = "\tlet x = 1;";
+    let sel = Selection {
+        start_x: 1,
+        start_y: 0,
+        end_x: 4,
+        end_y: 0,
+    };
+    assert_eq!(selection_columns(&sel, line, 0, 4), Some((4, 7)));
+    assert_eq!(selection_columns(&sel, line, 0, 8), Some((8, 11)));
+
+    let sel = Selection {
+        start_x: 0,
+        start_y: 0,
+        end_x: 1,
+        end_y: 2,
+    };
+    assert_eq!(selection_columns(&sel, line, 0, 4), Some((0, 14)));
+    assert_eq!(selection_columns(&sel, line, 1, 4), Some((0, 14)));
+    assert_eq!(selection_columns(&sel, line, 2, 4), Some((0, 4)));
+    assert_eq!(selection_columns(&sel, line, 3, 4), None);
+}
+
+#[test]
+fn word_occurrences_use_byte_offsets_on_multibyte_lines() {
+    let lines = vec![
+        "let größe = 1;".to_string(),
+        "größe + größe".to_string(),
+        "größer".to_string(),
+    ];
+    assert_eq!(
+        word_occurrences(&lines, 0..3, "größe", 80, 4, ""),
+        [(0, 4, 11), (1, 0, 7), (1, 10, 17)]
+    );
+    assert_eq!(word_occurrences(&lines, 1..2, "größe", 80, 4, "").len(), 2);
+
+    let wide = vec!["αβ αβ αβ".to_string()];
+    assert_eq!(
+        word_occurrences(&wide, 0..1, "αβ", 4, 4, ""),
+        [(0, 0, 4), (0, 5, 9)]
+    );
+}
+
+#[test]
+fn help_fits_small_terminals_and_lists_each_key_once() {
+    for term_h in [12, 24, 40] {
+        assert!(help_visible_rows(term_h) + HELP_CHROME_ROWS <= term_h as usize);
+    }
+    assert_eq!(help_visible_rows(24), 14);
+    assert_eq!(help_visible_rows(200), HELP_ROWS);
+    assert_eq!(help_visible_rows(4), 0);
+
+    let mut keys: Vec<&str> = HELP_BINDINGS.iter().map(|&(key, _)| key).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    assert_eq!(keys.len(), HELP_BINDINGS.len());
+    let tab_rows = HELP_BINDINGS
+        .iter()
+        .filter(|(key, _)| key.starts_with("Tab"));
+    assert_eq!(tab_rows.count(), 1);
+}
+
+#[test]
+fn save_age_rounds_down_at_unit_boundaries() {
+    let now = Local::now();
+    let ago = |secs| format_save_age(Some(now - Duration::seconds(secs)), now);
+    assert_eq!(format_save_age(None, now), "never saved");
+    assert_eq!(ago(59), "saved 59s ago");
+    assert_eq!(ago(60), "saved 1m ago");
+    assert_eq!(ago(3599), "saved 59m ago");
+    assert_eq!(ago(3600), "saved 1h ago");
+    assert_eq!(ago(86400), "saved 1d ago");
+    assert_eq!(ago(-5), "saved 0s ago");
+}
+
+#[test]
+fn terminal_restore_runs_each_step_once_in_order() {
+    let restored = AtomicBool::new(false);
+    let mut steps = Vec::new();
+    restore_terminal_with(&restored, |step| steps.push(step));
+    restore_terminal_with(&restored, |step| steps.push(step));
+
+    let position = |wanted| steps.iter().position(|&step| step == wanted).unwrap();
+    let count = |wanted| steps.iter().filter(|&&step| step == wanted).count();
+    assert_eq!(count(ShutdownStep::LeaveAlternateScreen), 1);
+    assert_eq!(count(ShutdownStep::DisableRawMode), 1);
+    assert!(position(ShutdownStep::LeaveAlternateScreen) < position(ShutdownStep::DisableRawMode));
+    assert_eq!(steps.last(), Some(&ShutdownStep::DisableRawMode));
+}
+
+#[test]
+fn color_editor_rows_stay_inside_the_box() {
+    for h in [3, 5, 6, 8, 12, 24] {
+        for idx in [0, 7, 24] {
+            let (box_h, visible, offset) = color_editor_window(h, 25, idx, 0);
+            assert!(box_h <= h);
+            assert!(visible + 5 <= box_h as usize || visible == 0);
+            assert!(offset + visible <= 25 || visible == 0);
+            if visible > 0 {
+                assert!((offset..offset + visible).contains(&idx));
+            }
+        }
+    }
+    assert_eq!(
+        color_editor_window(24, 25, 0, 0),
+        (15, COLOR_EDITOR_ROWS, 0)
+    );
+    assert_eq!(color_editor_window(8, 25, 9, 0), (8, 3, 7));
+    assert_eq!(color_editor_window(24, 4, 0, 0), (9, 4, 0));
+}